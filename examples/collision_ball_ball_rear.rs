@@ -3,7 +3,7 @@ use ggez::graphics;
 use ggez::conf::{WindowMode};
 use ggez::event::{self, EventHandler, KeyCode, KeyMods};
 use nalgebra::{Point2, Vector2};
-use crasball::game::{Ball,GameState};
+use crasball::game::{Ball,Edge,Facing,GameState,LiveArea};
 
 const DESIRED_FPS: u32 = 60;
 const SCREEN_SIZE: (f32, f32) = (400.0, 400.0);
@@ -29,24 +29,52 @@ struct CrasballGame {
 }
 
 fn create_initial_game_state() -> GameState {
-    GameState {
-        balls: vec![
-            Ball {
-                radius: 20.0,
-                position: Point2::new(100.0, 200.0),
-                movement: Vector2::new(100.0, 0.0)
-            },
-            Ball {
-                radius: 20.0,
-                position: Point2::new(141.0, 150.0),
-                movement: Vector2::new(
-                    (5000.0 as f32).sqrt(),
-                    (5000.0 as f32).sqrt()
-                )
-            }
-        ],
-        edges: Vec::new()
-    }
+    let balls = vec![
+        Ball {
+            radius: 20.0,
+            position: Point2::new(100.0, 200.0),
+            movement: Vector2::new(100.0, 0.0),
+            mass: None,
+            speed: None
+        },
+        Ball {
+            radius: 20.0,
+            position: Point2::new(141.0, 150.0),
+            movement: Vector2::new(
+                5000.0_f32.sqrt(),
+                5000.0_f32.sqrt()
+            ),
+            mass: None,
+            speed: None
+        }
+    ];
+
+    let edges = vec![
+        Edge {
+            a: Point2::new(0, 0),
+            b: Point2::new(400, 0),
+            n: Facing::Down
+        },
+        Edge {
+            a: Point2::new(400, 0),
+            b: Point2::new(400, 400),
+            n: Facing::Left
+        },
+        Edge {
+            a: Point2::new(400, 400),
+            b: Point2::new(0, 400),
+            n: Facing::Up
+        },
+        Edge {
+            a: Point2::new(0, 400),
+            b: Point2::new(0, 0),
+            n: Facing::Right
+        }
+    ];
+
+    GameState::new(vec![
+        LiveArea::new(balls, edges).expect("Screen edges should be valid")
+    ])
 }
 
 impl CrasballGame {
@@ -0,0 +1,31 @@
+use std::fs;
+use std::process;
+use crasball::replay::{Replay};
+
+// Plays a replay saved from the game without opening a window, and checks it
+// ends in the same state it did when it was recorded
+fn main() {
+    let path = match std::env::args().nth(1) {
+        Some(path) => path,
+        None => {
+            println!("Usage: cargo run --example play_replay -- <replay file>");
+            process::exit(2);
+        }
+    };
+
+    let bytes = fs::read(&path).unwrap_or_else(|e| {
+        println!("Could not read {}: {}", path, e);
+        process::exit(2);
+    });
+
+    match Replay::from_bytes(&bytes).and_then(|replay| replay.play()) {
+        Ok(state) => println!(
+            "Replay verified: level {}, {} walls, {} lives left",
+            state.level, state.walls.len(), state.lives
+        ),
+        Err(e) => {
+            println!("Replay failed: {}", e);
+            process::exit(1);
+        }
+    }
+}
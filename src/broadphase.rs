@@ -0,0 +1,115 @@
+use nalgebra::{convert, Point2};
+
+use crate::game::{Ball, Rectangle};
+
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub enum Broadphase {
+    BruteForce,
+    Grid
+}
+
+// A uniform grid over a live area's bounding box, with each ball stored in
+// the cell holding its centre. As long as the cells are at least as wide as
+// the widest ball, two balls can only overlap if their cells are neighbours.
+// Centres outside the grid are clamped into the cells around its border,
+// which keeps that true
+pub struct BallGrid {
+    pub cell_size: f32,
+    origin: Point2<f32>,
+    columns: usize,
+    rows: usize,
+    // The first ball in each cell, with the rest linked through next
+    heads: Vec<Option<usize>>,
+    next: Vec<Option<usize>>,
+    ball_cells: Vec<Option<usize>>
+}
+
+impl BallGrid {
+    pub fn new(bounds: Rectangle, cell_size: f32, ball_count: usize) -> BallGrid {
+        let size = bounds.bottom_right - bounds.top_left;
+        let columns = (size.x as f32 / cell_size).ceil().max(1.0) as usize;
+        let rows = (size.y as f32 / cell_size).ceil().max(1.0) as usize;
+
+        BallGrid {
+            cell_size,
+            origin: convert(bounds.top_left),
+            columns,
+            rows,
+            heads: vec![None; columns * rows],
+            next: vec![None; ball_count],
+            ball_cells: vec![None; ball_count]
+        }
+    }
+
+    fn column_and_row(&self, p: Point2<f32>) -> (usize, usize) {
+        let cell = |v: f32, origin: f32, count: usize| {
+            (((v - origin) / self.cell_size).floor().max(0.0) as usize).min(count - 1)
+        };
+
+        (cell(p.x, self.origin.x, self.columns), cell(p.y, self.origin.y, self.rows))
+    }
+
+    fn cell(&self, ball: &Ball) -> usize {
+        let (column, row) = self.column_and_row(ball.position);
+
+        row * self.columns + column
+    }
+
+    pub fn insert(&mut self, index: usize, ball: &Ball) {
+        let cell = self.cell(ball);
+
+        self.next[index] = self.heads[cell];
+        self.heads[cell] = Some(index);
+        self.ball_cells[index] = Some(cell);
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        let cell = match self.ball_cells[index].take() {
+            Some(cell) => cell,
+            None => return
+        };
+
+        if self.heads[cell] == Some(index) {
+            self.heads[cell] = self.next[index];
+            return;
+        }
+
+        let mut current = self.heads[cell];
+
+        while let Some(i) = current {
+            if self.next[i] == Some(index) {
+                self.next[i] = self.next[index];
+                return;
+            }
+
+            current = self.next[i];
+        }
+    }
+
+    // Only needs to do anything if the ball has moved into a different cell
+    pub fn update(&mut self, index: usize, ball: &Ball) {
+        if self.ball_cells[index] != Some(self.cell(ball)) {
+            self.remove(index);
+            self.insert(index, ball);
+        }
+    }
+
+    // Every ball in the same or a neighbouring cell, in no particular order
+    pub fn candidates<'a>(&'a self, ball: &Ball) -> impl Iterator<Item = usize> + 'a {
+        let (column, row) = self.column_and_row(ball.position);
+        let columns = column.saturating_sub(1)..=(column + 1).min(self.columns - 1);
+        let rows = row.saturating_sub(1)..=(row + 1).min(self.rows - 1);
+
+        rows
+            .flat_map(move |y| columns.clone().map(move |x| y * self.columns + x))
+            .flat_map(move |cell| {
+                let mut current = self.heads[cell];
+
+                std::iter::from_fn(move || {
+                    let i = current?;
+                    current = self.next[i];
+                    Some(i)
+                })
+            })
+    }
+}
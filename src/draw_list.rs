@@ -0,0 +1,137 @@
+use nalgebra::{convert, Point2};
+
+use crate::game::{GameState, Rectangle, WallHalfState};
+
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub struct Colour {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32
+}
+
+impl Colour {
+    pub const BLACK: Colour = Colour { r: 0.0, g: 0.0, b: 0.0, a: 1.0 };
+    pub const RED: Colour = Colour { r: 1.0, g: 0.0, b: 0.0, a: 1.0 };
+    pub const BLUE: Colour = Colour { r: 0.0, g: 0.0, b: 1.0, a: 1.0 };
+    pub const GREEN: Colour = Colour { r: 0.0, g: 0.6, b: 0.0, a: 1.0 };
+    pub const GREY: Colour = Colour { r: 0.6, g: 0.6, b: 0.6, a: 1.0 };
+}
+
+// Everything is in logical coordinates, the same space as the game geometry
+#[derive(Clone,Debug,PartialEq)]
+pub enum DrawCommand {
+    Outline {
+        points: Vec<Point2<f32>>,
+        colour: Colour
+    },
+    Rectangle {
+        top_left: Point2<f32>,
+        bottom_right: Point2<f32>,
+        colour: Colour
+    },
+    Circle {
+        centre: Point2<f32>,
+        radius: f32,
+        colour: Colour
+    },
+    Text {
+        position: Point2<f32>,
+        text: String,
+        colour: Colour
+    }
+}
+
+pub const HUD_POSITION: (f32, f32) = (10.0, 10.0);
+
+// Indexed by half, so each half of a growing wall can be told apart
+pub const GROWING_WALL_COLOURS: [Colour; 2] = [Colour::BLUE, Colour::GREEN];
+
+fn rectangle(rectangle: Rectangle, colour: Colour) -> DrawCommand {
+    DrawCommand::Rectangle {
+        top_left: convert(rectangle.top_left),
+        bottom_right: convert(rectangle.bottom_right),
+        colour
+    }
+}
+
+impl GameState {
+    // Commands are in painting order, so later commands draw over earlier ones
+    pub fn draw_list(&self) -> Vec<DrawCommand> {
+        let mut commands = Vec::new();
+
+        for captured in self.captured.iter() {
+            for r in captured.rectangles() {
+                commands.push(rectangle(r, Colour::GREY));
+            }
+        }
+
+        for &wall in self.walls.iter() {
+            commands.push(rectangle(wall, Colour::BLACK));
+        }
+
+        for area in self.live_areas.iter() {
+            commands.push(DrawCommand::Outline {
+                points: area.edges.iter().map(|e| convert(e.a)).collect(),
+                colour: Colour::BLACK
+            });
+        }
+
+        if let Some(wall) = &self.growing_wall {
+            for (half, &colour) in wall.halves.iter().zip(GROWING_WALL_COLOURS.iter()) {
+                if half.state != WallHalfState::Growing {
+                    continue;
+                }
+
+                let (top_left, bottom_right) = half.built();
+
+                // Nothing to draw until the half has started growing
+                if top_left.x == bottom_right.x || top_left.y == bottom_right.y {
+                    continue;
+                }
+
+                commands.push(DrawCommand::Rectangle {
+                    top_left,
+                    bottom_right,
+                    colour
+                });
+            }
+        }
+
+        for ball in self.balls() {
+            commands.push(DrawCommand::Circle {
+                centre: ball.position,
+                radius: ball.radius,
+                colour: Colour::RED
+            });
+        }
+
+        commands.push(DrawCommand::Text {
+            position: Point2::new(HUD_POSITION.0, HUD_POSITION.1),
+            text: self.hud_text(),
+            colour: Colour::BLACK
+        });
+
+        commands
+    }
+
+    fn hud_text(&self) -> String {
+        let percent = self.captured_area() * 100 / self.playfield_area.max(1);
+
+        // Nothing to count down without a time limit
+        let time = if self.time_remaining.is_finite() {
+            format!("  Time {}", self.time_remaining.ceil())
+        } else {
+            String::new()
+        };
+
+        format!(
+            "Level {}  Lives {}{}  Captured {}%  Score {}",
+            self.level,
+            self.lives,
+            time,
+            percent,
+            self.score.total()
+        )
+    }
+}
@@ -0,0 +1,200 @@
+use nalgebra::{Point2};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::error::Error;
+use std::fmt;
+
+use crate::game::{GameEvent, GameState, LevelStatus, Orientation, Rectangle, Rules, WallPlacementError};
+use crate::replay::{Replay};
+use crate::scoring::{ScoreBreakdown};
+
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub enum Phase {
+    Title,
+    Playing,
+    Paused,
+    LevelComplete,
+    GameOver
+}
+
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub enum FlowEvent {
+    Start,
+    Pause,
+    Resume,
+    Continue,
+    Quit
+}
+
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub struct TransitionError {
+    pub phase: Phase,
+    pub event: FlowEvent
+}
+
+impl fmt::Display for TransitionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "cannot {:?} while in {:?}", self.event, self.phase)
+    }
+}
+
+impl Error for TransitionError {}
+
+// Lets a frontend react to the flow moving between phases, such as to play a
+// sound or change the cursor. The game is None while on the title screen.
+pub trait FlowHooks {
+    fn on_enter(&mut self, _phase: Phase, _game: Option<&GameState>) {}
+    fn on_exit(&mut self, _phase: Phase, _game: Option<&GameState>) {}
+}
+
+// Moves between the title, each level, and the end of the game, so that
+// frontends only need to pass on input and time
+pub struct Flow {
+    pub phase: Phase,
+    pub playfield: Rectangle,
+    pub rules: Rules,
+    pub game: Option<GameState>,
+    pub total_score: i64,
+    pub level_summary: Option<ScoreBreakdown>,
+    pub seed: u64,
+    pub replay: Option<Replay>,
+    rng: ChaCha8Rng,
+    hooks: Vec<Box<dyn FlowHooks>>
+}
+
+impl Flow {
+    // Every level is generated from the seed, so the same seed and the same
+    // input always play out the same way. ChaCha8 is used by name as the
+    // algorithm behind StdRng can change between platforms and rand versions
+    pub fn new(playfield: Rectangle, rules: Rules, seed: u64) -> Flow {
+        Flow {
+            phase: Phase::Title,
+            playfield,
+            rules,
+            game: None,
+            total_score: 0,
+            level_summary: None,
+            seed,
+            replay: None,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            hooks: Vec::new()
+        }
+    }
+
+    pub fn add_hooks(&mut self, hooks: Box<dyn FlowHooks>) {
+        self.hooks.push(hooks);
+    }
+
+    pub fn handle(&mut self, event: FlowEvent) -> Result<(), TransitionError> {
+        let to = match (self.phase, event) {
+            (Phase::Title, FlowEvent::Start) => Phase::Playing,
+            (Phase::Playing, FlowEvent::Pause) => Phase::Paused,
+            (Phase::Paused, FlowEvent::Resume) => Phase::Playing,
+            (Phase::LevelComplete, FlowEvent::Continue) => Phase::Playing,
+            (Phase::GameOver, FlowEvent::Continue) => Phase::Title,
+            (phase, FlowEvent::Quit) if phase != Phase::Title => Phase::Title,
+            (phase, event) => return Err(TransitionError { phase, event })
+        };
+
+        self.transition(to);
+
+        Ok(())
+    }
+
+    // Walls can only be started while playing, and are recorded in the replay
+    pub fn start_wall(
+        &mut self, click: Point2<f32>, orientation: Orientation
+    ) -> Result<(), WallPlacementError> {
+
+        let game = match (&mut self.game, self.phase) {
+            (Some(game), Phase::Playing) => game,
+            _ => return Err(WallPlacementError::NotPlaying)
+        };
+
+        if let Some(replay) = &mut self.replay {
+            replay.record_wall(click, orientation);
+        }
+
+        game.start_wall(click, orientation)
+    }
+
+    // The game only moves on while playing, and the flow follows it when the
+    // level is complete or the game is over. Returns the game's events
+    pub fn update(&mut self, delta: f32) -> Vec<GameEvent> {
+        if self.phase != Phase::Playing {
+            return Vec::new();
+        }
+
+        let (events, status) = match &mut self.game {
+            Some(game) => (game.update(delta), game.status()),
+            None => return Vec::new()
+        };
+
+        if let (Some(replay), Some(game)) = (&mut self.replay, &self.game) {
+            replay.record_frame(game);
+        }
+
+        match status {
+            LevelStatus::Complete => self.transition(Phase::LevelComplete),
+            LevelStatus::GameOver => self.transition(Phase::GameOver),
+            LevelStatus::Playing => ()
+        }
+
+        events
+    }
+
+    fn transition(&mut self, to: Phase) {
+        let from = self.phase;
+
+        for hooks in self.hooks.iter_mut() {
+            hooks.on_exit(from, self.game.as_ref());
+        }
+
+        self.phase = to;
+        self.enter(from, to);
+
+        for hooks in self.hooks.iter_mut() {
+            hooks.on_enter(to, self.game.as_ref());
+        }
+    }
+
+    fn enter(&mut self, from: Phase, to: Phase) {
+        match to {
+            Phase::Title => {
+                self.game = None;
+                self.replay = None;
+                self.total_score = 0;
+                self.level_summary = None;
+            },
+            Phase::Playing => {
+                let level = match (from, &self.game) {
+                    (Phase::LevelComplete, Some(game)) => game.level + 1,
+                    (Phase::Title, _) => 1,
+                    _ => return
+                };
+
+                // Each level gets its own seed so it can be replayed alone
+                let seed = self.rng.gen();
+                let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+                self.game = Some(GameState::new_level(level, self.playfield, self.rules, &mut rng));
+                self.replay = Some(Replay::new(self.rules, seed, level, self.playfield));
+                self.level_summary = None;
+            },
+            Phase::Paused => (),
+            Phase::LevelComplete => {
+                if let Some(game) = &self.game {
+                    let summary = game.level_summary();
+
+                    self.total_score += summary.total();
+                    self.level_summary = Some(summary);
+                }
+            },
+            Phase::GameOver => {
+                if let Some(game) = &self.game {
+                    self.total_score += game.score.total();
+                }
+            }
+        }
+    }
+}
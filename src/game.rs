@@ -1,17 +1,122 @@
-use ggez::{Context, GameResult};
-use ggez::graphics::{self, Color};
 use nalgebra::{convert, Point2, Vector2};
+use rand::Rng;
+use crate::broadphase::{BallGrid, Broadphase};
+use crate::scoring::{self, ScoreBreakdown, ScoreRules};
+use std::error::Error;
+use std::fmt;
+
+pub const WALL_THICKNESS: i16 = 20;
+pub const BALL_RADIUS: f32 = 20.0;
 
 #[derive(Clone,Copy,Debug,PartialEq)]
 pub struct Ball {
     pub radius: f32,
     pub position: Point2<f32>,
-    pub movement: Vector2<f32>
+    pub movement: Vector2<f32>,
+    pub mass: Option<f32>,
+    // A ball with a set speed keeps it through collisions with other balls,
+    // which only change its direction
+    pub speed: Option<f32>
+}
+
+impl Ball {
+    // Without an explicit mass, a ball weighs as much as its area
+    pub fn mass(&self) -> f32 {
+        self.mass.unwrap_or(self.radius * self.radius)
+    }
+
+    // A ball brought to a stop has no direction left to keep, so it moves off
+    // along the given unit vector instead
+    fn keep_speed(&mut self, fallback: Vector2<f32>) {
+        let speed = match self.speed {
+            Some(speed) => speed,
+            None => return
+        };
+
+        let current = self.movement.norm();
+
+        self.movement = if current > 0.0 {
+            self.movement * (speed / current)
+        } else {
+            fallback * speed
+        };
+    }
+}
+
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub enum TimeOutRule {
+    LoseLife,
+    GameOver
+}
+
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub struct Rules {
+    pub clear_percent: i64,
+    pub base_ball_speed: f32,
+    pub ball_speed_per_level: f32,
+    pub wall_speed: f32,
+    pub base_time_limit: f32,
+    pub time_limit_per_level: f32,
+    pub on_time_out: TimeOutRule,
+    pub broadphase: Broadphase,
+    pub scoring: ScoreRules
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules {
+            clear_percent: 75,
+            base_ball_speed: 90.0,
+            ball_speed_per_level: 10.0,
+            wall_speed: 200.0,
+            base_time_limit: 60.0,
+            time_limit_per_level: 20.0,
+            on_time_out: TimeOutRule::LoseLife,
+            broadphase: Broadphase::Grid,
+            scoring: ScoreRules::default()
+        }
+    }
+}
+
+impl Rules {
+    pub fn time_limit(&self, level: u32) -> f32 {
+        self.base_time_limit + self.time_limit_per_level * level as f32
+    }
+
+    pub fn ball_speed(&self, level: u32) -> f32 {
+        self.base_ball_speed + self.ball_speed_per_level * level as f32
+    }
+}
+
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub enum LevelStatus {
+    Playing,
+    Complete,
+    GameOver
+}
+
+// Ball and edge numbers are indices into the live area at the time of the
+// event. Speeds are how fast the ball was closing on whatever it hit
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub enum GameEvent {
+    BallHitEdge { area: usize, ball: usize, edge: usize, speed: f32 },
+    BallHitCorner { area: usize, ball: usize, corner: Point2<i16>, speed: f32 },
+    BallHitBall { area: usize, first: usize, second: usize, speed: f32 },
+    WallDestroyed { half: usize, area: usize, ball: usize },
+    WallCompleted { half: usize, wall: Rectangle }
 }
 
 pub struct GameState {
-    pub balls: Vec<Ball>,
-    pub edges: Vec<Edge>
+    pub rules: Rules,
+    pub level: u32,
+    pub lives: u32,
+    pub time_remaining: f32,
+    pub playfield_area: i64,
+    pub live_areas: Vec<LiveArea>,
+    pub captured: Vec<CapturedArea>,
+    pub walls: Vec<Rectangle>,
+    pub growing_wall: Option<GrowingWall>,
+    pub score: ScoreBreakdown
 }
 
 #[derive(Debug,PartialEq)]
@@ -20,299 +125,761 @@ pub struct LiveArea {
     pub edges: Vec<Edge>
 }
 
-fn is_point_on_edge(p: Point2<i16>, e: &Edge) -> bool {
-    p >= e.a && p <= e.b ||
-    p >= e.b && p <= e.a
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub struct Rectangle {
+    pub top_left: Point2<i16>,
+    pub bottom_right: Point2<i16>
+}
+
+impl Rectangle {
+    // In the order expected by add_wall
+    pub fn corners(&self) -> [Point2<i16>; 4] {
+        [
+            self.top_left,
+            Point2::new(self.bottom_right.x, self.top_left.y),
+            self.bottom_right,
+            Point2::new(self.top_left.x, self.bottom_right.y)
+        ]
+    }
+}
+
+#[derive(Debug,PartialEq)]
+pub struct CapturedArea {
+    pub edges: Vec<Edge>,
+    pub area: i64
+}
+
+impl CapturedArea {
+    pub fn rectangles(&self) -> Vec<Rectangle> {
+        decompose_into_rectangles(&self.edges)
+    }
+}
+
+#[derive(Debug,PartialEq)]
+pub struct WallOutcome {
+    pub live_areas: Vec<LiveArea>,
+    pub captured: Vec<CapturedArea>
 }
 
-fn is_point_on_any_edge(p: Point2<i16>, edges: &[Edge]) -> (bool, usize) {
-    for (i, edge) in edges.iter().enumerate() {
-        if is_point_on_edge(p, edge) {
-            return (true, i);
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub enum LiveAreaError {
+    TooFewEdges,
+    OpenLoop { edge: usize },
+    DiagonalEdge { edge: usize },
+    SelfIntersection { first: usize, second: usize },
+    WrongWinding,
+    InconsistentFacing { edge: usize }
+}
+
+impl fmt::Display for LiveAreaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::TooFewEdges => write!(f, "a live area needs at least 4 edges"),
+            Self::OpenLoop { edge } => write!(f, "edge {} does not end where the next edge starts", edge),
+            Self::DiagonalEdge { edge } => write!(f, "edge {} is not horizontal or vertical", edge),
+            Self::SelfIntersection { first, second } => write!(f, "edges {} and {} cross or overlap", first, second),
+            Self::WrongWinding => write!(f, "edges go anticlockwise instead of clockwise"),
+            Self::InconsistentFacing { edge } => write!(f, "edge {} does not face into the area", edge)
         }
     }
+}
+
+impl Error for LiveAreaError {}
 
-    (false, 0)
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub enum WallPlacementError {
+    OutsideLiveAreas,
+    WallInProgress,
+    EnclosesHole,
+    CoversBall { ball: usize },
+    NotPlaying
 }
 
-// fn wrap_new_points_i(i: usize) -> usize {
-//     (3 - (((0 - i) % 4) - 1)) % 4
-// }
+impl fmt::Display for WallPlacementError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::OutsideLiveAreas => write!(f, "walls can only be placed inside a live area"),
+            Self::WallInProgress => write!(f, "only one wall can grow at a time"),
+            Self::EnclosesHole => write!(f, "walls must touch the outline of the live area"),
+            Self::CoversBall { ball } => write!(f, "the wall would cover ball {}", ball),
+            Self::NotPlaying => write!(f, "walls can only be started while playing")
+        }
+    }
+}
 
-impl LiveArea {
+impl Error for WallPlacementError {}
+
+// Touching at a single point is allowed, as add_wall can produce outlines that
+// meet themselves at a vertex
+fn edges_intersect(e1: &Edge, e2: &Edge) -> bool {
+    let (x1_min, x1_max) = (e1.a.x.min(e1.b.x), e1.a.x.max(e1.b.x));
+    let (y1_min, y1_max) = (e1.a.y.min(e1.b.y), e1.a.y.max(e1.b.y));
+    let (x2_min, x2_max) = (e2.a.x.min(e2.b.x), e2.a.x.max(e2.b.x));
+    let (y2_min, y2_max) = (e2.a.y.min(e2.b.y), e2.a.y.max(e2.b.y));
+
+    let x_overlap = x1_max.min(x2_max) as i32 - x1_min.max(x2_min) as i32;
+    let y_overlap = y1_max.min(y2_max) as i32 - y1_min.max(y2_min) as i32;
+
+    if x_overlap < 0 || y_overlap < 0 {
+        return false;
+    }
+
+    let e1_vertical = x1_min == x1_max;
+    let e2_vertical = x2_min == x2_max;
 
-    fn ball_is_inside(&self, ball: Ball) -> bool {
-        let (horizontal, vertical): (Vec<Edge>, Vec<Edge>) = self.edges
+    if e1_vertical == e2_vertical {
+        x_overlap + y_overlap > 0
+    } else {
+        let (horizontal, vertical) = if e1_vertical { (e2, e1) } else { (e1, e2) };
+        let x = vertical.a.x;
+        let y = horizontal.a.y;
+
+        x != horizontal.a.x && x != horizontal.b.x &&
+        y != vertical.a.y && y != vertical.b.y
+    }
+}
+
+// Shoelace formula, which is positive for clockwise edges as y points down
+fn polygon_area(edges: &[Edge]) -> i64 {
+    edges
+        .iter()
+        .map(|e| e.a.x as i64 * e.b.y as i64 - e.b.x as i64 * e.a.y as i64)
+        .sum::<i64>() / 2
+}
+
+// Splits a polygon into vertical slabs between neighbouring vertices. Every
+// horizontal edge crossing a slab spans it entirely, so pairing them off from
+// the top gives the inside spans. Matching spans in neighbouring slabs are
+// merged to keep the count down
+fn decompose_into_rectangles(edges: &[Edge]) -> Vec<Rectangle> {
+    let mut xs: Vec<i16> = edges.iter().map(|e| e.a.x).collect();
+    xs.sort_unstable();
+    xs.dedup();
+
+    let mut rectangles: Vec<Rectangle> = Vec::new();
+
+    for slab in xs.windows(2) {
+        let (left, right) = (slab[0], slab[1]);
+
+        let mut ys: Vec<i16> = edges
             .iter()
-            .partition(|&e| match e.n {
-                Facing::Down => true,
-                Facing::Left => false,
-                Facing::Up => true,
-                Facing::Right => false,
+            .filter(|e| is_horizontal(e) && e.a.x.min(e.b.x) <= left && e.a.x.max(e.b.x) >= right)
+            .map(|e| e.a.y)
+            .collect();
+        ys.sort_unstable();
+
+        for span in ys.chunks_exact(2) {
+            let neighbour = rectangles.iter_mut().find(|r| {
+                r.bottom_right.x == left && r.top_left.y == span[0] && r.bottom_right.y == span[1]
             });
 
-        horizontal.iter().filter(|&e| find_intersection(convert(e.a), convert(e.b), ball.position, ball.position + Vector2::new(0.0, 1024.0)).0).count() % 2 == 1 &&
-        vertical.iter().filter(|&e| find_intersection(convert(e.a), convert(e.b), ball.position, ball.position + Vector2::new(1024.0, 0.0)).0).count() % 2 == 1
+            match neighbour {
+                Some(rectangle) => rectangle.bottom_right.x = right,
+                None => rectangles.push(Rectangle {
+                    top_left: Point2::new(left, span[0]),
+                    bottom_right: Point2::new(right, span[1])
+                })
+            }
+        }
     }
 
-    // TODO: ITS TIME TO GENERALISE THIS MONSTER!!!
-    pub fn add_wall(
-        self,
-        top_left: Point2<i16>,
-        top_right: Point2<i16>,
-        bottom_right: Point2<i16>,
-        bottom_left: Point2<i16>
-    ) -> Vec<LiveArea> {
+    rectangles
+}
+
+fn edges_contain_point(edges: &[Edge], p: Point2<f32>) -> bool {
+    let mut crossings = 0;
+
+    for e in edges.iter() {
+        let (x1, x2) = (e.a.x.min(e.b.x) as f32, e.a.x.max(e.b.x) as f32);
+        let (y1, y2) = (e.a.y.min(e.b.y) as f32, e.a.y.max(e.b.y) as f32);
+
+        if x1 <= p.x && p.x <= x2 && y1 <= p.y && p.y <= y2 {
+            return true;
+        }
+
+        if x1 == x2 && x1 > p.x && y1 <= p.y && p.y < y2 {
+            crossings += 1;
+        }
+    }
+
+    crossings % 2 == 1
+}
+
+// Uses doubled coordinates so the centre of a cell is always a whole number,
+// and never lies on the same line as any vertex
+fn is_cell_inside(edges: &[Edge], centre_x: i32, centre_y: i32) -> bool {
+    edges
+        .iter()
+        .filter(|e| e.a.x == e.b.x)
+        .filter(|e| {
+            let x = e.a.x as i32 * 2;
+            let (y1, y2) = (e.a.y as i32 * 2, e.b.y as i32 * 2);
+
+            x > centre_x && y1.min(y2) < centre_y && centre_y < y1.max(y2)
+        })
+        .count() % 2 == 1
+}
+
+fn direction(a: Point2<i16>, b: Point2<i16>) -> (i16, i16) {
+    ((b.x - a.x).signum(), (b.y - a.y).signum())
+}
 
-        let mut output_areas: Vec<LiveArea> = Vec::default();
+fn facing_from_direction(d: (i16, i16)) -> Facing {
+    match d {
+        (1, 0) => Facing::Down,
+        (0, 1) => Facing::Left,
+        (-1, 0) => Facing::Up,
+        _ => Facing::Right
+    }
+}
+
+// Where outlines touch at a single point there is more than one way onwards,
+// so prefer turning right, then straight on, to hug the current cell
+fn next_segment(
+    segments: &[(Point2<i16>, Point2<i16>)], used: &[bool], current: usize
+) -> usize {
+    let (from, to) = segments[current];
+    let (dx, dy) = direction(from, to);
+
+    let turn_priority = |d: (i16, i16)| {
+        if d == (-dy, dx) {
+            0
+        } else if d == (dx, dy) {
+            1
+        } else {
+            2
+        }
+    };
+
+    (0..segments.len())
+        .filter(|&i| !used[i] && segments[i].0 == to)
+        .min_by_key(|&i| turn_priority(direction(segments[i].0, segments[i].1)))
+        .expect("Region outline is not closed")
+}
+
+fn edges_from_points(points: &[Point2<i16>]) -> Vec<Edge> {
+    (0..points.len())
+        .map(|i| {
+            let a = points[i];
+            let b = points[(i + 1) % points.len()];
+
+            Edge {
+                a,
+                b,
+                n: facing_from_direction(direction(a, b))
+            }
+        })
+        .collect()
+}
+
+fn is_horizontal(edge: &Edge) -> bool {
+    edge.a.y == edge.b.y
+}
 
-        let mut current_area = LiveArea {
-            balls: Vec::default(),
-            edges: Vec::default()
+// Zero length edges are dropped, and neighbouring edges along the same line
+// are combined. If the second doubles back over the first only the part left
+// uncovered is kept, so an edge never lies within another. Edges start from
+// the left-most, top-most point, which matches the fixtures.
+fn normalise_edges(mut edges: Vec<Edge>) -> Vec<Edge> {
+
+    loop {
+        edges.retain(|e| e.a != e.b);
+
+        let len = edges.len();
+
+        if len < 2 {
+            break;
+        }
+
+        let collinear = (0..len).find(|&i| {
+            is_horizontal(&edges[i]) == is_horizontal(&edges[(i + 1) % len])
+        });
+
+        let i = match collinear {
+            Some(i) => i,
+            None => break
+        };
+
+        let next = edges[(i + 1) % len];
+        let merged = Edge {
+            a: edges[i].a,
+            b: next.b,
+            n: if direction(edges[i].a, next.b) == direction(next.a, next.b) {
+                next.n
+            } else {
+                edges[i].n
+            }
         };
 
-        let new_points = [top_left, top_right, bottom_right, bottom_left];
+        edges[i] = merged;
+        edges.remove((i + 1) % len);
+    }
+
+    let start = (0..edges.len())
+        .min_by_key(|&i| (edges[i].a.x, edges[i].a.y))
+        .unwrap_or(0);
 
-        let mut ignore_until = 0;
-        for i in 0..self.edges.len() {
+    edges.rotate_left(start);
 
-            if ignore_until > i {
+    edges
+}
+
+// None if the region has a hole in it, which a single loop of edges can't
+// represent
+fn trace_region(
+    regions: &[Vec<Option<usize>>], region: usize, xs: &[i16], ys: &[i16]
+) -> Option<Vec<Edge>> {
+
+    let in_region = |i: isize, j: isize| {
+        i >= 0 && j >= 0 &&
+        (i as usize) < regions.len() && (j as usize) < regions[0].len() &&
+        regions[i as usize][j as usize] == Some(region)
+    };
+
+    // Each side of a cell which doesn't border another cell of the region is
+    // part of its outline, directed clockwise around the cell
+    let mut segments: Vec<(Point2<i16>, Point2<i16>)> = Vec::new();
+
+    for i in 0..regions.len() as isize {
+        for j in 0..regions[0].len() as isize {
+
+            if !in_region(i, j) {
                 continue;
             }
 
-            let edge = self.edges[i];
-            let rest = &self.edges[(i+1)..];
+            let top_left = Point2::new(xs[i as usize], ys[j as usize]);
+            let top_right = Point2::new(xs[i as usize + 1], ys[j as usize]);
+            let bottom_right = Point2::new(xs[i as usize + 1], ys[j as usize + 1]);
+            let bottom_left = Point2::new(xs[i as usize], ys[j as usize + 1]);
 
-            // Point A is the first clockwise point that might lie on the
-            // current egde, based on the Facing direction
-            let point_a_index = match edge.n {
-                Facing::Down => 0,
-                Facing::Left => 1,
-                Facing::Up => 2,
-                Facing::Right => 3,
-            };
+            if !in_region(i, j - 1) {
+                segments.push((top_left, top_right));
+            }
 
-            let point_a = new_points[point_a_index];
-            let point_b = new_points[(point_a_index + 1) % 4];
-            let point_c = new_points[(point_a_index + 2) % 4];
-            let point_d = new_points[(point_a_index + 3) % 4];
+            if !in_region(i + 1, j) {
+                segments.push((top_right, bottom_right));
+            }
 
-            if edge.b == point_d {
+            if !in_region(i, j + 1) {
+                segments.push((bottom_right, bottom_left));
+            }
 
-                current_area.edges.push(
-                    Edge {
-                        a: edge.a,
-                        b: point_c,
-                        n: edge.n
-                    }
-                );
-
-                let (connects_edges, connect_i) = is_point_on_any_edge(point_c, rest);
-                if connects_edges {
-                    ignore_until = connect_i + 1 + (i + 1);
-
-                    current_area.edges.push(
-                        Edge {
-                            a: point_c,
-                            b: rest[connect_i].b,
-                            n: rest[connect_i].n
-                        }
-                    );
-
-                    let mut other_area = LiveArea {
-                        balls: Vec::default(),
-                        edges: Vec::default()
-                    };
-
-                    other_area.edges.push(
-                        Edge {
-                            a: point_a,
-                            b: rest[0].b,
-                            n: rest[0].n
-                        }
-                    );
-
-                    for j in i+2..ignore_until-1 {
-                        other_area.edges.push(self.edges[j]);
-                    }
+            if !in_region(i - 1, j) {
+                segments.push((bottom_left, top_left));
+            }
+        }
+    }
 
-                    other_area.edges.push(
-                        Edge {
-                            a: rest[connect_i].a,
-                            b: point_b,
-                            n: rest[connect_i].n
-                        }
-                    );
-
-                    other_area.edges.push(
-                        Edge {
-                            a: point_b,
-                            b: point_a,
-                            n: rest[connect_i].n.clockwise()
-                        }
-                    );
-
-                    for ball in self.balls.clone() {
-                        if other_area.ball_is_inside(ball) {
-                            other_area.balls.push(ball);
-                        }
-                    }
+    // The first segment is the top of the left-most, top-most cell so the
+    // first loop is always the outside of the region
+    let mut used = vec![false; segments.len()];
+    let mut loops: Vec<Vec<Point2<i16>>> = Vec::new();
 
-                    if other_area.balls.len() > 0 {
-                        output_areas.push(other_area);
-                    }
+    while let Some(start) = used.iter().position(|&u| !u) {
 
-                } else {
+        let mut points = vec![segments[start].0];
+        let mut current = start;
+        used[start] = true;
+
+        while segments[current].1 != points[0] {
+            current = next_segment(&segments, &used, current);
+            used[current] = true;
+            points.push(segments[current].0);
+        }
+
+        loops.push(points);
+    }
+
+    let mut outline = loops.remove(0);
+
+    // Loops that touch the outline at a point are walked as a detour from
+    // that point. Any left over are holes which don't touch it at all
+    while let Some((loop_i, outline_i, loop_point_i)) = loops
+        .iter()
+        .enumerate()
+        .find_map(|(loop_i, points)| {
+            points.iter().enumerate().find_map(|(loop_point_i, p)| {
+                outline
+                    .iter()
+                    .position(|o| o == p)
+                    .map(|outline_i| (loop_i, outline_i, loop_point_i))
+            })
+        })
+    {
+        let mut detour = loops.remove(loop_i);
+        detour.rotate_left(loop_point_i);
+        detour.push(detour[0]);
+
+        let rest = outline.split_off(outline_i + 1);
+        outline.extend(detour.into_iter().skip(1));
+        outline.extend(rest);
+    }
+
+    if !loops.is_empty() {
+        return None;
+    }
+
+    Some(edges_from_points(&outline))
+}
+
+// The area left after taking away the wall is found by cutting the polygon
+// into a grid along every x and y used by the polygon or the wall. The cells
+// inside the polygon but not the wall are grouped into connected regions,
+// each of which is traced back into a loop of edges. None if any region is
+// left with a hole in it.
+fn subtract_rectangle(
+    edges: &[Edge],
+    top_left: Point2<i16>,
+    bottom_right: Point2<i16>
+) -> Option<Vec<Vec<Edge>>> {
+
+    let mut xs: Vec<i16> = edges.iter().flat_map(|e| vec![e.a.x, e.b.x]).collect();
+    xs.push(top_left.x);
+    xs.push(bottom_right.x);
+    xs.sort();
+    xs.dedup();
+
+    let mut ys: Vec<i16> = edges.iter().flat_map(|e| vec![e.a.y, e.b.y]).collect();
+    ys.push(top_left.y);
+    ys.push(bottom_right.y);
+    ys.sort();
+    ys.dedup();
+
+    let columns = xs.len() - 1;
+    let rows = ys.len() - 1;
+
+    let mut cells = vec![vec![false; rows]; columns];
+
+    for (i, column) in cells.iter_mut().enumerate() {
+        for (j, cell) in column.iter_mut().enumerate() {
+
+            let is_wall = xs[i] >= top_left.x && xs[i + 1] <= bottom_right.x &&
+                ys[j] >= top_left.y && ys[j + 1] <= bottom_right.y;
+
+            *cell = !is_wall && is_cell_inside(
+                edges,
+                xs[i] as i32 + xs[i + 1] as i32,
+                ys[j] as i32 + ys[j + 1] as i32
+            );
+        }
+    }
 
-                    current_area.edges.push(
-                        Edge {
-                            a: point_c,
-                            b: point_b,
-                            n: edge.n.anticlockwise()
-                        }
-                    );
-
-                    current_area.edges.push(
-                        Edge {
-                            a: point_b,
-                            b: point_a,
-                            n: edge.n.opposite()
-                        }
-                    );
-
-                    current_area.edges.push(
-                        Edge {
-                            a: point_a,
-                            b: rest[0].b,
-                            n: edge.n.anticlockwise()
-                        }
-                    );
-
-                    ignore_until = i + 2;
+    let mut regions: Vec<Vec<Option<usize>>> = vec![vec![None; rows]; columns];
+    let mut region_count = 0;
+
+    // Searching by column then row finds regions in the same order as the
+    // starting point of their edges
+    for i in 0..columns {
+        for j in 0..rows {
+
+            if !cells[i][j] || regions[i][j].is_some() {
+                continue;
+            }
+
+            let mut to_visit = vec![(i, j)];
+
+            while let Some((x, y)) = to_visit.pop() {
+
+                if !cells[x][y] || regions[x][y].is_some() {
+                    continue;
                 }
 
-            } else if is_point_on_edge(point_a, &edge) {
-                current_area.edges.push(
-                    Edge {
-                        a: edge.a,
-                        b: point_a,
-                        n: edge.n
-                    }
-                );
+                regions[x][y] = Some(region_count);
 
-                current_area.edges.push(
-                    Edge {
-                        a: point_a,
-                        b: point_d,
-                        n: edge.n.clockwise()
-                    }
-                );
-
-                let (connects_edges, connect_i) = is_point_on_any_edge(point_d, rest);
-                if connects_edges {
-
-                    ignore_until = connect_i + 1 + (i + 1);
-
-                    current_area.edges.push(
-                        Edge {
-                            a: point_d,
-                            b: rest[connect_i].b,
-                            n: rest[connect_i].n
-                        }
-                    );
-
-                    let mut other_area = LiveArea {
-                        balls: Vec::default(),
-                        edges: Vec::default()
-                    };
-
-                    let mut skip_j = 1;
-                    let mut start_point = point_b;
-
-                    if point_b != edge.b {
-                        other_area.edges.push(
-                            Edge {
-                                a: point_b,
-                                b: edge.b,
-                                n: edge.n
-                            }
-                        );
-                    } else {
-                        skip_j += 1;
-                        start_point = rest[0].b;
-                    }
+                if x > 0 {
+                    to_visit.push((x - 1, y));
+                }
 
-                    for j in i+skip_j..ignore_until-1 {
-                        other_area.edges.push(self.edges[j]);
-                    }
+                if x + 1 < columns {
+                    to_visit.push((x + 1, y));
+                }
 
-                    other_area.edges.push(
-                        Edge {
-                            a: rest[connect_i].a,
-                            b: point_c,
-                            n: rest[connect_i].n
-                        }
-                    );
-
-                    other_area.edges.push(
-                        Edge {
-                            a: point_c,
-                            b: start_point,
-                            n: rest[connect_i].n.clockwise()
-                        }
-                    );
-
-                    other_area.edges.rotate_right(point_a_index);
-
-                    for ball in self.balls.clone() {
-                        if other_area.ball_is_inside(ball) {
-                            other_area.balls.push(ball);
-                        }
-                    }
+                if y > 0 {
+                    to_visit.push((x, y - 1));
+                }
 
-                    if other_area.balls.len() > 0 {
-                        output_areas.push(other_area);
-                    }
+                if y + 1 < rows {
+                    to_visit.push((x, y + 1));
+                }
+            }
+
+            region_count += 1;
+        }
+    }
+
+    (0..region_count)
+        .map(|region| trace_region(&regions, region, &xs, &ys))
+        .collect()
+}
 
+impl LiveArea {
+
+    pub fn new(balls: Vec<Ball>, edges: Vec<Edge>) -> Result<LiveArea, LiveAreaError> {
+        let live_area = LiveArea { balls, edges };
+
+        live_area.validate()?;
+
+        Ok(live_area.normalised())
+    }
+
+    pub fn normalise(&mut self) {
+        self.edges = normalise_edges(std::mem::take(&mut self.edges));
+    }
+
+    pub fn normalised(mut self) -> LiveArea {
+        self.normalise();
+        self
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.validate().is_ok()
+    }
+
+    // Edges must form a closed loop of horizontal and vertical edges, going
+    // clockwise, with each edge facing into the area
+    pub fn validate(&self) -> Result<(), LiveAreaError> {
+        let edges = &self.edges;
+        let len = edges.len();
+
+        if len < 4 {
+            return Err(LiveAreaError::TooFewEdges);
+        }
+
+        for (i, edge) in edges.iter().enumerate() {
+            if edge.b != edges[(i + 1) % len].a {
+                return Err(LiveAreaError::OpenLoop { edge: i });
+            }
+
+            if edge.a.x != edge.b.x && edge.a.y != edge.b.y {
+                return Err(LiveAreaError::DiagonalEdge { edge: i });
+            }
+        }
+
+        for i in 0..len {
+            for j in (i + 1)..len {
+                let adjacent = j == i + 1 || (i == 0 && j == len - 1);
+
+                let intersects = if adjacent {
+                    let (first, second) = if j == i + 1 { (i, j) } else { (j, i) };
+                    let d1 = direction(edges[first].a, edges[first].b);
+                    let d2 = direction(edges[second].a, edges[second].b);
+
+                    d1 == (-d2.0, -d2.1) && d1 != (0, 0)
                 } else {
+                    edges_intersect(&edges[i], &edges[j])
+                };
 
-                    current_area.edges.push(
-                        Edge {
-                            a: point_d,
-                            b: point_c,
-                            n: edge.n
-                        }
-                    );
-                    current_area.edges.push(
-                        Edge {
-                            a: point_c,
-                            b: point_b,
-                            n: edge.n.anticlockwise()
-                        }
-                    );
-                    current_area.edges.push(
-                        Edge {
-                            a: point_b,
-                            b: edge.b,
-                            n: edge.n
-                        }
-                    );
+                if intersects {
+                    return Err(LiveAreaError::SelfIntersection { first: i, second: j });
                 }
-            } else {
-                current_area.edges.push(edge)
             }
         }
 
-        for ball in self.balls.clone() {
-            if current_area.ball_is_inside(ball) {
-                current_area.balls.push(ball);
+        if polygon_area(edges) <= 0 {
+            return Err(LiveAreaError::WrongWinding);
+        }
+
+        for (i, edge) in edges.iter().enumerate() {
+            if edge.a != edge.b && edge.n != facing_from_direction(direction(edge.a, edge.b)) {
+                return Err(LiveAreaError::InconsistentFacing { edge: i });
             }
         }
 
-        output_areas.insert(0, current_area);
+        Ok(())
+    }
+
+    pub fn area(&self) -> i64 {
+        polygon_area(&self.edges)
+    }
+
+    pub fn perimeter(&self) -> i64 {
+        self.edges
+            .iter()
+            .map(|e| {
+                (e.b.x as i64 - e.a.x as i64).abs() + (e.b.y as i64 - e.a.y as i64).abs()
+            })
+            .sum()
+    }
+
+    pub fn bounding_box(&self) -> Rectangle {
+        let xs = || self.edges.iter().map(|e| e.a.x);
+        let ys = || self.edges.iter().map(|e| e.a.y);
+
+        Rectangle {
+            top_left: Point2::new(xs().min().unwrap_or(0), ys().min().unwrap_or(0)),
+            bottom_right: Point2::new(xs().max().unwrap_or(0), ys().max().unwrap_or(0))
+        }
+    }
+
+    pub fn centroid(&self) -> Point2<f32> {
+        let mut x = 0.0;
+        let mut y = 0.0;
+
+        for e in self.edges.iter() {
+            let (ax, ay) = (e.a.x as f64, e.a.y as f64);
+            let (bx, by) = (e.b.x as f64, e.b.y as f64);
+            let cross = ax * by - bx * ay;
+
+            x += (ax + bx) * cross;
+            y += (ay + by) * cross;
+        }
+
+        let six_area = 6.0 * self.area() as f64;
+
+        Point2::new((x / six_area) as f32, (y / six_area) as f32)
+    }
+
+    // Points exactly on an edge count as inside. Otherwise a ray is cast to
+    // the right, and counts the vertical edges it crosses, with each edge
+    // including its top point but not its bottom so a ray through a vertex
+    // is only counted once. All coordinates are whole numbers so this is exact.
+    pub fn contains_point(&self, p: Point2<f32>) -> bool {
+        edges_contain_point(&self.edges, p)
+    }
+
+    pub fn ball_is_inside(&self, ball: Ball) -> bool {
+        self.contains_point(ball.position)
+    }
+
+    // Touching an edge still counts as inside
+    pub fn ball_disc_is_inside(&self, ball: Ball) -> bool {
+        self.contains_point(ball.position) &&
+        self.edges.iter().all(|e| {
+            let a: Point2<f32> = convert(e.a);
+            let b: Point2<f32> = convert(e.b);
+            let closest = Point2::new(
+                ball.position.x.max(a.x.min(b.x)).min(a.x.max(b.x)),
+                ball.position.y.max(a.y.min(b.y)).min(a.y.max(b.y))
+            );
+
+            (ball.position - closest).norm() >= ball.radius
+        })
+    }
+
+    // The click is snapped to the wall thickness grid across the wall, and
+    // the wall runs both ways along it until the nearest edge
+    pub fn wall_from_click(
+        &self, click: Point2<f32>, orientation: Orientation
+    ) -> Option<Rectangle> {
+
+        if !self.contains_point(click) {
+            return None;
+        }
+
+        let snap = |v: f32| (v / WALL_THICKNESS as f32).floor() as i16 * WALL_THICKNESS;
 
-        output_areas
+        let (across, along) = match orientation {
+            Orientation::Horizontal => (snap(click.y), click.x),
+            Orientation::Vertical => (snap(click.x), click.y)
+        };
+
+        // Edges which block the wall run across it, and overlap its width
+        let blocking: Vec<(i16, i16, i16)> = self.edges
+            .iter()
+            .filter(|e| is_horizontal(e) == (orientation == Orientation::Vertical))
+            .map(|e| match orientation {
+                Orientation::Horizontal => (e.a.x, e.a.y.min(e.b.y), e.a.y.max(e.b.y)),
+                Orientation::Vertical => (e.a.y, e.a.x.min(e.b.x), e.a.x.max(e.b.x))
+            })
+            .filter(|&(_, min, max)| min < across + WALL_THICKNESS && max > across)
+            .collect();
+
+        let start = blocking.iter().map(|b| b.0).filter(|&p| p as f32 <= along).max()?;
+        let end = blocking.iter().map(|b| b.0).filter(|&p| p as f32 >= along).min()?;
+
+        if start >= end {
+            return None;
+        }
+
+        Some(match orientation {
+            Orientation::Horizontal => Rectangle {
+                top_left: Point2::new(start, across),
+                bottom_right: Point2::new(end, across + WALL_THICKNESS)
+            },
+            Orientation::Vertical => Rectangle {
+                top_left: Point2::new(across, start),
+                bottom_right: Point2::new(across + WALL_THICKNESS, end)
+            }
+        })
     }
+
+    // The wall can cross vertices, touch any number of edges or cut the area
+    // into any number of pieces. Pieces without balls are captured. A wall
+    // which would leave a hole in the area, or cover the centre of a ball,
+    // is rejected.
+    pub fn add_wall(
+        &self,
+        top_left: Point2<i16>,
+        top_right: Point2<i16>,
+        bottom_right: Point2<i16>,
+        bottom_left: Point2<i16>
+    ) -> Result<WallOutcome, WallPlacementError> {
+
+        let corners = [top_left, top_right, bottom_right, bottom_left];
+
+        let min = Point2::new(
+            corners.iter().map(|p| p.x).min().unwrap(),
+            corners.iter().map(|p| p.y).min().unwrap()
+        );
+
+        let max = Point2::new(
+            corners.iter().map(|p| p.x).max().unwrap(),
+            corners.iter().map(|p| p.y).max().unwrap()
+        );
+
+        let mut outcome = WallOutcome {
+            live_areas: Vec::default(),
+            captured: Vec::default()
+        };
+
+        let regions = subtract_rectangle(&self.edges, min, max)
+            .ok_or(WallPlacementError::EnclosesHole)?;
+
+        for edges in regions {
+
+            let mut area = LiveArea {
+                balls: Vec::default(),
+                edges: normalise_edges(edges)
+            };
+
+            for ball in self.balls.iter() {
+                if area.ball_is_inside(*ball) {
+                    area.balls.push(*ball);
+                }
+            }
+
+            if area.balls.is_empty() {
+                outcome.captured.push(CapturedArea {
+                    area: polygon_area(&area.edges),
+                    edges: area.edges
+                });
+            } else {
+                outcome.live_areas.push(area);
+            }
+        }
+
+        // Every ball inside the area is inside one of the pieces, unless the
+        // wall is on top of it
+        let homed = |ball: &Ball| outcome.live_areas.iter().any(|a| a.balls.contains(ball));
+
+        if let Some(ball) = self.balls.iter().position(|b| !homed(b)) {
+            return Err(WallPlacementError::CoversBall { ball });
+        }
+
+        Ok(outcome)
+    }
+}
+
+// Finds the live area that was clicked, and the wall that would be built in it
+pub fn wall_from_click(
+    live_areas: &[LiveArea], click: Point2<f32>, orientation: Orientation
+) -> Result<(usize, Rectangle), WallPlacementError> {
+
+    live_areas
+        .iter()
+        .enumerate()
+        .find_map(|(i, area)| area.wall_from_click(click, orientation).map(|wall| (i, wall)))
+        .ok_or(WallPlacementError::OutsideLiveAreas)
 }
 
 #[derive(Clone,Copy,Debug,PartialEq)]
@@ -322,6 +889,12 @@ pub struct Edge {
     pub n: Facing
 }
 
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub enum Orientation {
+    Horizontal,
+    Vertical
+}
+
 #[derive(Clone,Copy,Debug,PartialEq)]
 pub enum Facing {
     Down,
@@ -349,7 +922,7 @@ impl Facing {
         }
     }
 
-    fn opposite(self) -> Self {
+    pub fn opposite(self) -> Self {
         match self {
             Self::Down => Self::Up,
             Self::Up => Self::Down,
@@ -359,160 +932,827 @@ impl Facing {
     }
 }
 
-fn find_intersection(
-    a1: Point2<f32>, a2: Point2<f32>, b1: Point2<f32>, b2: Point2<f32>
-) -> (bool, Point2<f32>)
-{
-    let sa = *(a2 - a1);
-    let sb = *(b2 - b1);
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub enum WallHalfState {
+    Growing,
+    Complete,
+    Destroyed
+}
 
-    let d1 = *(a1 - b1);
+// One half of a growing wall, which starts at the click point and grows in
+// the direction given until it fills its target
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub struct WallHalf {
+    pub target: Rectangle,
+    pub direction: Facing,
+    pub length: f32,
+    pub state: WallHalfState
+}
 
-    let s = (-sa.y * d1.x + sa.x * d1.y) / (-sb.x * sa.y + sa.x * sb.y);
-    let t = (sb.x * d1.y - sb.y * d1.x) / (-sb.x * sa.y + sa.x * sb.y);
+impl WallHalf {
 
-    if s >= 0.0 && s <= 1.0 && t >= 0.0 && t <= 1.0 {
-        (true, a1 + ((a2 - a1) * t))
-    } else {
-        (false, Point2::origin())
+    fn target_length(&self) -> f32 {
+        match self.direction {
+            Facing::Up | Facing::Down => (self.target.bottom_right.y - self.target.top_left.y) as f32,
+            Facing::Left | Facing::Right => (self.target.bottom_right.x - self.target.top_left.x) as f32
+        }
+    }
+
+    // The top left and bottom right of the part built so far
+    pub fn built(&self) -> (Point2<f32>, Point2<f32>) {
+        let mut top_left: Point2<f32> = convert(self.target.top_left);
+        let mut bottom_right: Point2<f32> = convert(self.target.bottom_right);
+        let length = self.length.min(self.target_length());
+
+        match self.direction {
+            Facing::Up => top_left.y = bottom_right.y - length,
+            Facing::Down => bottom_right.y = top_left.y + length,
+            Facing::Left => top_left.x = bottom_right.x - length,
+            Facing::Right => bottom_right.x = top_left.x + length
+        }
+
+        (top_left, bottom_right)
+    }
+
+    fn is_touching(&self, ball: &Ball) -> bool {
+        let (top_left, bottom_right) = self.built();
+        let closest = Point2::new(
+            ball.position.x.max(top_left.x).min(bottom_right.x),
+            ball.position.y.max(top_left.y).min(bottom_right.y)
+        );
+
+        (ball.position - closest).norm() < ball.radius
     }
 }
 
-fn reflect_vector(i: Vector2<f32>, n: Vector2<f32>) -> Vector2<f32> {
-    let raw_vector = i - n * (n.dot(&i) * 2.0);
-    let raw_magnitude = raw_vector.norm();
+#[derive(Clone,Debug,PartialEq)]
+pub struct GrowingWall {
+    pub speed: f32,
+    pub halves: [WallHalf; 2]
+}
 
-    raw_vector * (100.0 / raw_magnitude)
+#[derive(Debug,Default,PartialEq)]
+pub struct GrowingWallUpdate {
+    pub events: Vec<GameEvent>,
+    pub walls: Vec<Rectangle>,
+    pub captured: Vec<CapturedArea>,
+    pub lives_lost: u32
 }
 
+// Nothing is changed unless the wall can be added to every live area it
+// crosses
+fn commit_wall(
+    wall: Rectangle, live_areas: &mut Vec<LiveArea>, captured: &mut Vec<CapturedArea>
+) -> Result<(), WallPlacementError> {
+    let [top_left, top_right, bottom_right, bottom_left] = wall.corners();
+    let mut outcomes = Vec::new();
+
+    for (i, area) in live_areas.iter().enumerate() {
+        let bounds = area.bounding_box();
+        let overlaps = bounds.top_left.x < bottom_right.x && bounds.bottom_right.x > top_left.x &&
+            bounds.top_left.y < bottom_right.y && bounds.bottom_right.y > top_left.y;
+
+        if overlaps {
+            outcomes.push((i, area.add_wall(top_left, top_right, bottom_right, bottom_left)?));
+        }
+    }
+
+    let mut output_areas = Vec::new();
+    let mut outcomes = outcomes.into_iter().peekable();
+
+    for (i, area) in live_areas.drain(..).enumerate() {
+        match outcomes.next_if(|(j, _)| *j == i) {
+            Some((_, outcome)) => {
+                output_areas.extend(outcome.live_areas);
+                captured.extend(outcome.captured);
+            },
+            None => output_areas.push(area)
+        }
+    }
+
+    *live_areas = output_areas;
+
+    Ok(())
+}
+
+impl GrowingWall {
+
+    // The wall is split at the grid line nearest the click, with the first
+    // half growing up or left and the second down or right
+    pub fn new(
+        wall: Rectangle, click: Point2<f32>, orientation: Orientation, speed: f32
+    ) -> GrowingWall {
+
+        let snap = |v: f32, min: i16, max: i16| {
+            ((v / WALL_THICKNESS as f32).round() as i16 * WALL_THICKNESS).max(min).min(max)
+        };
+
+        let (first, second, directions) = match orientation {
+            Orientation::Horizontal => {
+                let split = snap(click.x, wall.top_left.x, wall.bottom_right.x);
+
+                (
+                    Rectangle {
+                        top_left: wall.top_left,
+                        bottom_right: Point2::new(split, wall.bottom_right.y)
+                    },
+                    Rectangle {
+                        top_left: Point2::new(split, wall.top_left.y),
+                        bottom_right: wall.bottom_right
+                    },
+                    (Facing::Left, Facing::Right)
+                )
+            },
+            Orientation::Vertical => {
+                let split = snap(click.y, wall.top_left.y, wall.bottom_right.y);
+
+                (
+                    Rectangle {
+                        top_left: wall.top_left,
+                        bottom_right: Point2::new(wall.bottom_right.x, split)
+                    },
+                    Rectangle {
+                        top_left: Point2::new(wall.top_left.x, split),
+                        bottom_right: wall.bottom_right
+                    },
+                    (Facing::Up, Facing::Down)
+                )
+            }
+        };
+
+        // A click next to either end leaves that half with nothing to build,
+        // so it starts out complete and is never added
+        let half = |target: Rectangle, direction| {
+            let size = target.bottom_right - target.top_left;
+
+            WallHalf {
+                target,
+                direction,
+                length: 0.0,
+                state: if size.x == 0 || size.y == 0 {
+                    WallHalfState::Complete
+                } else {
+                    WallHalfState::Growing
+                }
+            }
+        };
+
+        GrowingWall {
+            speed,
+            halves: [half(first, directions.0), half(second, directions.1)]
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.halves.iter().all(|h| h.state != WallHalfState::Growing)
+    }
+
+    // A half touched by any ball is destroyed, even in the step it reaches
+    // its target. Otherwise it is added to the live areas it crosses once it
+    // reaches its target
+    pub fn update(&mut self, delta: f32, live_areas: &mut Vec<LiveArea>) -> GrowingWallUpdate {
+        let mut output = GrowingWallUpdate::default();
+
+        for (i, half) in self.halves.iter_mut().enumerate() {
+
+            if half.state != WallHalfState::Growing {
+                continue;
+            }
+
+            half.length += self.speed * delta;
+
+            // Includes the piece grown this step, even if it finished the half
+            let touching = live_areas.iter().enumerate().find_map(|(a, area)| {
+                area.balls.iter().position(|b| half.is_touching(b)).map(|b| (a, b))
+            });
+
+            if let Some((area, ball)) = touching {
+                half.state = WallHalfState::Destroyed;
+                output.lives_lost += 1;
+                output.events.push(GameEvent::WallDestroyed { half: i, area, ball });
+                continue;
+            }
+
+            if half.length < half.target_length() {
+                continue;
+            }
+
+            // No ball touches the half and it always reaches an edge, so it
+            // can't cover a ball or enclose a hole. If it somehow does it is
+            // lost rather than left growing forever
+            if commit_wall(half.target, live_areas, &mut output.captured).is_err() {
+                half.state = WallHalfState::Destroyed;
+                continue;
+            }
+
+            half.state = WallHalfState::Complete;
+            output.walls.push(half.target);
+            output.events.push(GameEvent::WallCompleted { half: i, wall: half.target });
+        }
+
+        output
+    }
+}
+
+// The normal must be a unit vector, so the speed is unchanged
+fn reflect_vector(i: Vector2<f32>, n: Vector2<f32>) -> Vector2<f32> {
+    i - n * (n.dot(&i) * 2.0)
+}
+
+// A one dimensional elastic collision along the unit normal between the
+// centres, which can point either way. Movement across it is unchanged
 fn elastic_collision_vector(
-    v1: Vector2<f32>, x1: Point2<f32>,
-    v2: Vector2<f32>, x2: Point2<f32>
+    v1: Vector2<f32>, m1: f32, v2: Vector2<f32>, m2: f32, n: Vector2<f32>
 ) -> Vector2<f32> {
-    let x2_to_x1 = x1 - x2;
-    let raw_vector = v1 - ((v1 - v2).dot(&x2_to_x1) / x2_to_x1.norm().powi(2)) * x2_to_x1;
-    let raw_magnitude = raw_vector.norm();
+    v1 - n * (2.0 * m2 / (m1 + m2) * (v1 - v2).dot(&n))
+}
 
-    raw_vector * (100.0 / raw_magnitude)
+fn edge_normal(facing: Facing) -> Vector2<f32> {
+    match facing {
+        Facing::Down => Vector2::new(0.0, 1.0),
+        Facing::Left => Vector2::new(-1.0, 0.0),
+        Facing::Up => Vector2::new(0.0, -1.0),
+        Facing::Right => Vector2::new(1.0, 0.0)
+    }
 }
 
-impl GameState {
-    pub fn update(&mut self, delta: f32) {
-        let mut moved_balls: Vec<&mut Ball> = Vec::new();
+enum ImpactKind {
+    Edge(usize),
+    Corner(Point2<i16>)
+}
 
-        for ball in &mut self.balls {
+struct Impact {
+    time: f32,
+    normal: Vector2<f32>,
+    kind: ImpactKind
+}
 
-            let to_move = ball.movement * delta;
-            let mut newpos = ball.position + to_move;
-            let mut next_move = ball.movement;
+// The time until the ball's disc touches the edge. A ball already overlapping
+// the edge while heading into it hits it immediately
+fn edge_impact(ball: &Ball, index: usize, edge: &Edge) -> Option<Impact> {
+    let normal = edge_normal(edge.n);
+    let approach = -ball.movement.dot(&normal);
 
-            for edge in self.edges.iter() {
+    if approach <= 0.0 {
+        return None;
+    }
 
-                let edge_a: Point2<f32> = convert(edge.a);
-                let edge_b: Point2<f32> = convert(edge.b);
+    let a: Point2<f32> = convert(edge.a);
+    let b: Point2<f32> = convert(edge.b);
+    let distance = (ball.position - a).dot(&normal);
 
-                let edge_n: Vector2<f32> = match edge.n {
-                    Facing::Down => Vector2::new(0.0, 1.0),
-                    Facing::Left => Vector2::new(-1.0, 0.0),
-                    Facing::Up => Vector2::new(0.0, -1.0),
-                    Facing::Right => Vector2::new(1.0, 0.0)
-                };
+    // Behind the edge, so it can't be this edge the ball is moving towards
+    if distance < 0.0 {
+        return None;
+    }
 
-                let distance_to_a = (edge_a - newpos).norm();
+    let time = ((distance - ball.radius) / approach).max(0.0);
+    let contact = ball.position + ball.movement * time;
+    let along = (contact - a).dot(&(b - a)) / (b - a).norm_squared();
 
-                if distance_to_a < ball.radius {
+    if !(0.0..=1.0).contains(&along) {
+        return None;
+    }
+
+    Some(Impact { time, normal, kind: ImpactKind::Edge(index) })
+}
 
-                    let overshoot = ball.radius - distance_to_a;
-                    let remaining = overshoot / to_move.norm();
+// The time until the ball's disc touches the vertex, solving
+// |position + movement * t - vertex| = radius for the earliest t
+fn corner_impact(ball: &Ball, corner: Point2<i16>) -> Option<Impact> {
+    let vertex: Point2<f32> = convert(corner);
+    let offset = ball.position - vertex;
+    let b = offset.dot(&ball.movement);
 
-                    newpos = newpos - to_move * remaining;
+    if b >= 0.0 {
+        return None;
+    }
 
-                    next_move = reflect_vector(ball.movement, newpos - edge_a);
+    let a = ball.movement.norm_squared();
+    let c = offset.norm_squared() - ball.radius * ball.radius;
 
-                    newpos = newpos + next_move * remaining * delta;
+    let time = if c < 0.0 {
+        0.0
+    } else {
+        let discriminant = b * b - a * c;
 
-                    break;
+        if discriminant < 0.0 {
+            return None;
+        }
 
-                }
+        (-b - discriminant.sqrt()) / a
+    };
 
-                let offset = edge_n * -ball.radius;
-                let (intersects, offset_intersect_point) = find_intersection(
-                    ball.position + offset, ball.position + to_move + offset,
-                    edge_a, edge_b
-                );
-                if intersects {
-                    let intersect_point = offset_intersect_point - offset;
+    let contact = ball.position + ball.movement * time;
 
-                    next_move = reflect_vector(ball.movement, edge_n);
+    Some(Impact {
+        time,
+        normal: (contact - vertex).normalize(),
+        kind: ImpactKind::Corner(corner)
+    })
+}
+
+// Every vertex is the start of exactly one edge. Turning left into it, the
+// vertex pokes into the live area and is convex to the ball, which rolls round
+// it. Turning right it is a concave corner of the live area, which the ball
+// can only reach by hitting both edges
+fn convex_vertices(edges: &[Edge]) -> impl Iterator<Item = Point2<i16>> + '_ {
+    edges
+        .iter()
+        .enumerate()
+        .filter(move |(i, e)| {
+            let previous = &edges[(i + edges.len() - 1) % edges.len()];
+            let (x1, y1) = direction(previous.a, previous.b);
+            let (x2, y2) = direction(e.a, e.b);
+
+            x1 * y2 - y1 * x2 < 0
+        })
+        .map(|(_, e)| e.a)
+}
 
-                    let travelled = intersect_point - ball.position;
-                    let remaining = travelled.norm() / to_move.norm();
+const MAX_BOUNCES_PER_STEP: usize = 8;
 
-                    newpos = intersect_point + next_move * remaining * delta;
+// Impacts closer together than this are treated as simultaneous
+const IMPACT_TOLERANCE: f32 = 1e-4;
 
-                    break;
-                }
+// Advances to the earliest impact, bounces, and repeats with whatever time is
+// left. Impacts at the same moment, such as both edges of a concave corner,
+// are all bounced off together. If the bounce limit is reached the ball gives
+// up the rest of the step where it is, rather than risk leaving its live area
+fn move_ball(
+    ball: &mut Ball, edges: &[Edge], delta: f32,
+    area: usize, index: usize, events: &mut Vec<GameEvent>
+) {
+    let mut remaining = delta;
+
+    for _ in 0..MAX_BOUNCES_PER_STEP {
+        let impacts: Vec<Impact> = edges
+            .iter()
+            .enumerate()
+            .filter_map(|(i, e)| edge_impact(ball, i, e))
+            .chain(convex_vertices(edges).filter_map(|v| corner_impact(ball, v)))
+            .filter(|impact| impact.time <= remaining)
+            .collect();
+
+        if impacts.is_empty() {
+            ball.position += ball.movement * remaining;
+            return;
+        }
+
+        let earliest = impacts.iter().map(|i| i.time).fold(f32::INFINITY, f32::min);
+
+        ball.position += ball.movement * earliest;
+        remaining -= earliest;
+
+        for impact in impacts.iter().filter(|i| i.time - earliest < IMPACT_TOLERANCE) {
+            let speed = -ball.movement.dot(&impact.normal);
+
+            // Already heading away after bouncing off a matching impact
+            if speed <= 0.0 {
+                continue;
             }
 
-            ball.position = newpos;
-            ball.movement = next_move;
+            ball.movement = reflect_vector(ball.movement, impact.normal);
 
-            for b2 in &mut moved_balls {
+            events.push(match impact.kind {
+                ImpactKind::Edge(edge) => GameEvent::BallHitEdge { area, ball: index, edge, speed },
+                ImpactKind::Corner(corner) => GameEvent::BallHitCorner { area, ball: index, corner, speed }
+            });
+        }
+    }
+}
 
-                let ball_to_b2 = ball.position - b2.position;
-                let distance_apart = ball_to_b2.norm();
+fn balls_overlap(b1: &Ball, b2: &Ball) -> bool {
+    (b1.position - b2.position).norm() < b1.radius + b2.radius
+}
+
+// Returns the speed the balls were closing at
+fn collide_balls(ball: &mut Ball, b2: &mut Ball, edges: &[Edge]) -> f32 {
+    let ball_to_b2 = b2.position - ball.position;
+    let distance_apart = ball_to_b2.norm();
 
-                if distance_apart < ball.radius + b2.radius {
+    // Balls exactly on top of each other are pulled apart sideways
+    let n = if distance_apart > 0.0 {
+        ball_to_b2 / distance_apart
+    } else {
+        Vector2::new(1.0, 0.0)
+    };
 
-                    let correction = (ball.radius + b2.radius - distance_apart) / 2.0;
+    let speed = (ball.movement - b2.movement).dot(&n);
 
-                    let new_ball_movement = elastic_collision_vector(ball.movement, ball.position, b2.movement, b2.position);
-                    let new_b2_movement = elastic_collision_vector(b2.movement, b2.position, ball.movement, ball.position);
+    // The overlap is split by inverse mass, so the lighter ball is pushed
+    // further
+    let (m1, m2) = (ball.mass(), b2.mass());
+    let overlap = ball.radius + b2.radius - distance_apart;
+    let ball_correction = overlap * m2 / (m1 + m2);
+    let b2_correction = overlap * m1 / (m1 + m2);
 
-                    ball.movement = new_ball_movement;
-                    ball.position = ball.position + ball.movement * (correction / ball.movement.norm());
+    let new_ball_movement = elastic_collision_vector(ball.movement, m1, b2.movement, m2, n);
+    let new_b2_movement = elastic_collision_vector(b2.movement, m2, ball.movement, m1, n);
 
-                    b2.movement = new_b2_movement;
-                    b2.position = b2.position + b2.movement * (correction / b2.movement.norm());
-                }
+    ball.movement = new_ball_movement;
+    b2.movement = new_b2_movement;
+    ball.keep_speed(-n);
+    b2.keep_speed(n);
+
+    // A ball is never pushed out of its live area, any overlap with an edge
+    // is bounced out of on the next step
+    let ball_position = ball.position - n * ball_correction;
+    let b2_position = b2.position + n * b2_correction;
+
+    if edges_contain_point(edges, ball_position) {
+        ball.position = ball_position;
+    }
 
+    if edges_contain_point(edges, b2_position) {
+        b2.position = b2_position;
+    }
+
+    speed
+}
+
+// The first ball from the given index, among those already moved, which
+// overlaps the ball being moved. The grid only narrows down the candidates,
+// so both paths always pick the same ball
+fn next_overlap(balls: &[Ball], moving: usize, from: usize, grid: Option<&BallGrid>) -> Option<usize> {
+    let overlaps = |&j: &usize| balls_overlap(&balls[moving], &balls[j]);
+
+    match grid {
+        Some(grid) => grid
+            .candidates(&balls[moving])
+            .filter(|&j| j >= from && j < moving)
+            .filter(overlaps)
+            .min(),
+        None => (from..moving).find(overlaps)
+    }
+}
+
+// Balls only collide with the edges of their own live area, and with the
+// other balls inside it. Each ball is moved in turn, then bounced off every
+// ball moved before it
+fn update_balls(
+    area: &mut LiveArea, delta: f32, broadphase: Broadphase,
+    area_index: usize, events: &mut Vec<GameEvent>
+) {
+    let bounds = area.bounding_box();
+    let LiveArea { balls, edges } = area;
+
+    // Cells line up with the wall grid, and are wide enough for any ball
+    let widest = balls.iter().map(|b| b.radius * 2.0).fold(0.0, f32::max);
+    let cell_size = (widest / WALL_THICKNESS as f32).ceil().max(1.0) * WALL_THICKNESS as f32;
+
+    let mut grid = match broadphase {
+        Broadphase::Grid => Some(BallGrid::new(bounds, cell_size, balls.len())),
+        Broadphase::BruteForce => None
+    };
+
+    for i in 0..balls.len() {
+
+        move_ball(&mut balls[i], edges, delta, area_index, i, events);
+
+        let mut from = 0;
+
+        while let Some(j) = next_overlap(balls, i, from, grid.as_ref()) {
+            let (moved, rest) = balls.split_at_mut(i);
+
+            let speed = collide_balls(&mut rest[0], &mut moved[j], edges);
+
+            events.push(GameEvent::BallHitBall { area: area_index, first: j, second: i, speed });
+
+            if let Some(grid) = &mut grid {
+                grid.update(j, &balls[j]);
             }
 
-            moved_balls.push(ball);
+            from = j + 1;
+        }
 
+        if let Some(grid) = &mut grid {
+            grid.insert(i, &balls[i]);
         }
+
     }
+}
 
-    pub fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
-        let rectangle = graphics::Mesh::new_rectangle(
-            ctx,
-            graphics::DrawMode::fill(),
-            graphics::Rect {
-                x: 390.0,
-                y: 300.0,
-                w: 20.0,
-                h: 300.0
-            },
-            graphics::BLACK,
-        )?;
+fn random_ball_position<R: Rng>(playfield: Rectangle, radius: f32, rng: &mut R) -> Point2<f32> {
+    let top_left: Point2<f32> = convert(playfield.top_left);
+    let bottom_right: Point2<f32> = convert(playfield.bottom_right);
+
+    Point2::new(
+        top_left.x + radius + rng.gen::<f32>() * (bottom_right.x - top_left.x - 2.0 * radius),
+        top_left.y + radius + rng.gen::<f32>() * (bottom_right.y - top_left.y - 2.0 * radius)
+    )
+}
+
+// Picks a point in the unit circle and scales it up, rather than taking the
+// sine and cosine of a random angle. Those come from the platform's maths
+// library and can differ between machines, while square roots are exact
+fn random_ball_movement<R: Rng>(velocity: f32, rng: &mut R) -> Vector2<f32> {
+    loop {
+        let base_movement = Vector2::new(rng.gen::<f32>() * 2.0 - 1.0, rng.gen::<f32>() * 2.0 - 1.0);
+        let base_magnitude = base_movement.norm();
+
+        if base_magnitude > 0.0 && base_magnitude <= 1.0 {
+            return base_movement * (velocity / base_magnitude);
+        }
+    }
+}
+
+// FNV-1a, fed little-endian bytes so the same state hashes the same on any
+// machine. Floats are hashed by their bits, so only identical states match
+struct StateHasher(u64);
+
+impl StateHasher {
+    fn new() -> StateHasher {
+        StateHasher(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn u32(&mut self, v: u32) {
+        self.bytes(&v.to_le_bytes());
+    }
+
+    fn i64(&mut self, v: i64) {
+        self.bytes(&v.to_le_bytes());
+    }
+
+    fn f32(&mut self, v: f32) {
+        self.u32(v.to_bits());
+    }
+
+    fn point(&mut self, p: Point2<i16>) {
+        self.bytes(&p.x.to_le_bytes());
+        self.bytes(&p.y.to_le_bytes());
+    }
+
+    fn edges(&mut self, edges: &[Edge]) {
+        self.u32(edges.len() as u32);
+
+        for edge in edges {
+            self.point(edge.a);
+            self.point(edge.b);
+            self.bytes(&[edge.n as u8]);
+        }
+    }
+
+    fn rectangle(&mut self, r: Rectangle) {
+        self.point(r.top_left);
+        self.point(r.bottom_right);
+    }
+}
+
+impl GameState {
+    // Sandbox states have no time limit, so they run until told otherwise
+    pub fn new(live_areas: Vec<LiveArea>) -> GameState {
+        GameState {
+            rules: Rules::default(),
+            level: 1,
+            lives: 2,
+            time_remaining: f32::INFINITY,
+            playfield_area: live_areas.iter().map(|a| a.area()).sum(),
+            live_areas,
+            captured: Vec::new(),
+            walls: Vec::new(),
+            growing_wall: None,
+            score: ScoreBreakdown::default()
+        }
+    }
+
+    // Level N starts with N + 1 balls and N + 1 lives
+    pub fn new_level<R: Rng>(level: u32, playfield: Rectangle, rules: Rules, rng: &mut R) -> GameState {
+        let balls = (0..=level)
+            .map(|_| Ball {
+                radius: BALL_RADIUS,
+                position: random_ball_position(playfield, BALL_RADIUS, rng),
+                movement: random_ball_movement(rules.ball_speed(level), rng),
+                mass: None,
+                speed: Some(rules.ball_speed(level))
+            })
+            .collect();
+
+        let edges = playfield
+            .corners()
+            .iter()
+            .enumerate()
+            .map(|(i, &a)| {
+                let b = playfield.corners()[(i + 1) % 4];
+
+                Edge {
+                    a,
+                    b,
+                    n: facing_from_direction(direction(a, b))
+                }
+            })
+            .collect();
+
+        let live_area = LiveArea::new(balls, edges).expect("Playfield should be a valid live area");
+
+        GameState {
+            rules,
+            level,
+            lives: level + 1,
+            time_remaining: rules.time_limit(level),
+            ..GameState::new(vec![live_area])
+        }
+    }
+
+    pub fn captured_area(&self) -> i64 {
+        self.playfield_area - self.live_areas.iter().map(|a| a.area()).sum::<i64>()
+    }
+
+    pub fn status(&self) -> LevelStatus {
+        let timed_out = self.time_remaining <= 0.0 &&
+            self.rules.on_time_out == TimeOutRule::GameOver;
 
-        graphics::draw(ctx, &rectangle, graphics::DrawParam::default())?;
+        if self.lives == 0 || timed_out {
+            LevelStatus::GameOver
+        } else if self.captured_area() * 100 >= self.rules.clear_percent * self.playfield_area {
+            LevelStatus::Complete
+        } else {
+            LevelStatus::Playing
+        }
+    }
+
+    pub fn balls(&self) -> impl Iterator<Item = &Ball> {
+        self.live_areas.iter().flat_map(|area| area.balls.iter())
+    }
 
-        for ball in self.balls.iter() {
+    // The wall is added to every live area it crosses, or to none of them
+    pub fn add_wall(&mut self, wall: Rectangle) -> Result<(), WallPlacementError> {
+        let mut captured = Vec::new();
 
-            let circle = graphics::Mesh::new_circle(
-                ctx,
-                graphics::DrawMode::fill(),
-                ball.position,
-                ball.radius,
-                0.5,
-                Color::new(1.0, 0.0, 0.0, 1.0),
-            )?;
+        commit_wall(wall, &mut self.live_areas, &mut captured)?;
+        self.walls.push(wall);
+        self.record_captured(captured);
 
-            graphics::draw(ctx, &circle, graphics::DrawParam::default())?;
+        Ok(())
+    }
 
+    // Only one wall can grow at a time. Clicks on walls which have already
+    // been built are outside every live area, so are rejected too
+    pub fn start_wall(
+        &mut self, click: Point2<f32>, orientation: Orientation
+    ) -> Result<(), WallPlacementError> {
+
+        if self.growing_wall.is_some() {
+            return Err(WallPlacementError::WallInProgress);
         }
 
+        let (_, wall) = wall_from_click(&self.live_areas, click, orientation)?;
+
+        self.growing_wall = Some(GrowingWall::new(wall, click, orientation, self.rules.wall_speed));
+
         Ok(())
     }
+
+    fn record_captured(&mut self, captured: Vec<CapturedArea>) {
+        self.score.captured += scoring::captured_points(&self.rules.scoring, &captured);
+        self.captured.extend(captured);
+    }
+
+    pub fn level_summary(&self) -> ScoreBreakdown {
+        scoring::level_summary(self)
+    }
+
+    // Nothing moves once the level is complete or the game is over. Returns
+    // everything that happened during the step, in the order it happened
+    pub fn update(&mut self, delta: f32) -> Vec<GameEvent> {
+        let mut events = Vec::new();
+
+        if self.status() != LevelStatus::Playing {
+            return events;
+        }
+
+        for (i, area) in self.live_areas.iter_mut().enumerate() {
+            update_balls(area, delta, self.rules.broadphase, i, &mut events);
+        }
+
+        self.rehome_balls();
+
+        if let Some(wall) = &mut self.growing_wall {
+            let update = wall.update(delta, &mut self.live_areas);
+
+            if wall.is_finished() {
+                self.growing_wall = None;
+            }
+
+            self.lives = self.lives.saturating_sub(update.lives_lost);
+            self.walls.extend(update.walls);
+            self.record_captured(update.captured);
+            events.extend(update.events);
+        }
+
+        self.update_timer(delta);
+
+        events
+    }
+
+    // Covers everything which changes during play, for checking that two
+    // runs of the simulation ended up in exactly the same state
+    pub fn state_hash(&self) -> u64 {
+        let mut h = StateHasher::new();
+
+        h.u32(self.level);
+        h.u32(self.lives);
+        h.f32(self.time_remaining);
+        h.i64(self.playfield_area);
+
+        h.u32(self.live_areas.len() as u32);
+
+        for area in self.live_areas.iter() {
+            h.edges(&area.edges);
+            h.u32(area.balls.len() as u32);
+
+            for ball in area.balls.iter() {
+                h.f32(ball.radius);
+                h.f32(ball.position.x);
+                h.f32(ball.position.y);
+                h.f32(ball.movement.x);
+                h.f32(ball.movement.y);
+                h.f32(ball.mass.unwrap_or(f32::NAN));
+                h.f32(ball.speed.unwrap_or(f32::NAN));
+            }
+        }
+
+        h.u32(self.captured.len() as u32);
+
+        for captured in self.captured.iter() {
+            h.edges(&captured.edges);
+            h.i64(captured.area);
+        }
+
+        h.u32(self.walls.len() as u32);
+
+        for &wall in self.walls.iter() {
+            h.rectangle(wall);
+        }
+
+        match &self.growing_wall {
+            Some(wall) => {
+                h.bytes(&[1]);
+                h.f32(wall.speed);
+
+                for half in wall.halves.iter() {
+                    h.rectangle(half.target);
+                    h.bytes(&[half.direction as u8, half.state as u8]);
+                    h.f32(half.length);
+                }
+            },
+            None => h.bytes(&[0])
+        }
+
+        h.i64(self.score.captured);
+        h.i64(self.score.fill_bonus);
+        h.i64(self.score.life_bonus);
+        h.i64(self.score.time_bonus);
+
+        h.0
+    }
+
+    // Running out of time either costs a life and restarts the clock, or
+    // ends the game, depending on the rules
+    fn update_timer(&mut self, delta: f32) {
+        if self.status() != LevelStatus::Playing || self.time_remaining.is_infinite() {
+            return;
+        }
+
+        self.time_remaining = (self.time_remaining - delta).max(0.0);
+
+        if self.time_remaining <= 0.0 && self.rules.on_time_out == TimeOutRule::LoseLife {
+            self.lives = self.lives.saturating_sub(1);
+
+            if self.lives > 0 {
+                self.time_remaining = self.rules.time_limit(self.level);
+            }
+        }
+    }
+
+    // A ball which has escaped its live area is moved into whichever live
+    // area now contains it
+    fn rehome_balls(&mut self) {
+        for i in 0..self.live_areas.len() {
+            let mut j = 0;
+
+            while j < self.live_areas[i].balls.len() {
+                let ball = self.live_areas[i].balls[j];
+
+                if !self.live_areas[i].ball_is_inside(ball) {
+                    let home = self.live_areas.iter().position(|a| a.ball_is_inside(ball));
+
+                    if let Some(k) = home {
+                        self.live_areas[i].balls.remove(j);
+                        self.live_areas[k].balls.push(ball);
+                        continue;
+                    }
+                }
+
+                j += 1;
+            }
+        }
+    }
 }
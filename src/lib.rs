@@ -1,4 +1,11 @@
 #[cfg(test)]
 mod tests;
 
+pub mod broadphase;
+pub mod draw_list;
+pub mod flow;
 pub mod game;
+#[cfg(feature = "ggez-frontend")]
+pub mod render;
+pub mod replay;
+pub mod scoring;
@@ -1,17 +1,26 @@
 use ggez::{graphics, Context, ContextBuilder, GameResult};
 use ggez::conf::{WindowMode};
-use ggez::event::{self, EventHandler};
+use ggez::event::{self, EventHandler, KeyCode, KeyMods};
 use ggez::input::mouse::{self, MouseButton};
 use ggez::timer;
-use nalgebra::{Point2, Vector2};
-use rand::{random};
-use std::f32::consts::{PI};
-use crasball::game::{Ball, Facing, GameState, Edge};
+use nalgebra::{Point2};
+use crasball::draw_list::{Colour, DrawCommand};
+use crasball::flow::{Flow, FlowEvent, Phase};
+use crasball::game::{Orientation, Rectangle, Rules};
+use crasball::render;
 
 const DESIRED_FPS: u32 = 60;
 const SCREEN_SIZE: (f32, f32) = (800.0, 600.0);
 
 fn main() {
+    // Pass the seed printed by an earlier run to play the same levels again
+    let seed = std::env::args()
+        .nth(1)
+        .and_then(|arg| arg.parse().ok())
+        .unwrap_or_else(rand::random);
+
+    println!("Seed: {}", seed);
+
     // Make a Context.
     let (mut ctx, mut event_loop) = ContextBuilder::new("dancras/crasball", "dancras")
         .window_mode(WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1))
@@ -21,7 +30,7 @@ fn main() {
     // Create an instance of your event handler.
     // Usually, you should provide it with the Context object to
     // use when setting your game up.
-    let mut my_game = CrasballGame::new(&mut ctx);
+    let mut my_game = CrasballGame::new(&mut ctx, seed);
 
     // Run!
     match event::run(&mut ctx, &mut event_loop, &mut my_game) {
@@ -35,125 +44,121 @@ enum CursorDirection {
     Vertical
 }
 
+impl CursorDirection {
+    fn orientation(&self) -> Orientation {
+        match self {
+            CursorDirection::Horizontal => Orientation::Horizontal,
+            CursorDirection::Vertical => Orientation::Vertical
+        }
+    }
+}
+
 struct CrasballGame {
     cursor_direction: CursorDirection,
-    state: GameState
+    flow: Flow
 }
 
 impl CrasballGame {
-    pub fn new(ctx: &mut Context) -> CrasballGame {
+    pub fn new(ctx: &mut Context, seed: u64) -> CrasballGame {
         // Load/create resources such as images here.
         mouse::set_cursor_type(ctx, mouse::MouseCursor::NsResize);
 
+        let playfield = Rectangle {
+            top_left: Point2::new(0, 0),
+            bottom_right: Point2::new(SCREEN_SIZE.0 as i16, SCREEN_SIZE.1 as i16)
+        };
+
         CrasballGame {
             cursor_direction: CursorDirection::Vertical,
-            state: GameState {
-                balls: vec![
-                    Ball {
-                        radius: 20.0,
-                        position: random_ball_position(20.0),
-                        movement: random_ball_movement(100.0)
-                    },
-                    Ball {
-                        radius: 20.0,
-                        position: Point2::new(350.0, 255.0),
-                        movement: Vector2::new(
-                            (5000.0 as f32).sqrt(),
-                            (5000.0 as f32).sqrt()
-                        )
-                    },
-                    Ball {
-                        radius: 20.0,
-                        position: random_ball_position(20.0),
-                        movement: random_ball_movement(100.0)
-                    },
-                    Ball {
-                        radius: 20.0,
-                        position: random_ball_position(20.0),
-                        movement: random_ball_movement(100.0)
-                    }
-                ],
-                edges: vec![
-                    Edge {
-                        a: Point2::new(0, 0),
-                        b: Point2::new(800, 0),
-                        n: Facing::Down
-                    },
-                    Edge {
-                        a: Point2::new(800, 0),
-                        b: Point2::new(800, 600),
-                        n: Facing::Left
-                    },
-                    Edge {
-                        a: Point2::new(800, 600),
-                        b: Point2::new(0, 600),
-                        n: Facing::Up
-                    },
-                    Edge {
-                        a: Point2::new(0, 600),
-                        b: Point2::new(0, 0),
-                        n: Facing::Right
-                    },
-
-                    // Test edge
-                    Edge {
-                        a: Point2::new(390, 300),
-                        b: Point2::new(410, 300),
-                        n: Facing::Up
-                    },
-                    Edge {
-                        a: Point2::new(410, 300),
-                        b: Point2::new(410, 600),
-                        n: Facing::Right
-                    },
-                    Edge {
-                        a: Point2::new(390, 300),
-                        b: Point2::new(390, 600),
-                        n: Facing::Left
-                    }
-                ]
-            }
+            flow: Flow::new(playfield, Rules::default(), seed)
         }
     }
-}
 
-fn random_ball_position(radius: f32) -> Point2<f32> {
+    // Saves the current level so it can be played back with the play_replay
+    // example, such as to attach to a bug report
+    fn save_replay(&self) {
+        let replay = match &self.flow.replay {
+            Some(replay) => replay,
+            None => {
+                println!("No level has been played yet, so there is no replay to save");
+                return;
+            }
+        };
 
-    Point2::new(
-        radius + random::<f32>() * (SCREEN_SIZE.0 - 2.0 * radius),
-        radius + random::<f32>() * (SCREEN_SIZE.1 - 2.0 * radius)
-    )
-}
+        let path = format!("crasball-{}-{}.replay", self.flow.seed, replay.level);
 
-fn random_ball_movement(velocity: f32) -> Vector2<f32> {
-    let angle = 2.0 * PI * random::<f32>();
-    let base_movement = Vector2::new(angle.sin(), angle.cos());
-    let base_magnitude = base_movement.norm();
+        match std::fs::write(&path, replay.to_bytes()) {
+            Ok(_) => println!("Saved replay to {}", path),
+            Err(e) => println!("Could not save replay: {}", e)
+        }
+    }
 
-    base_movement * (velocity / base_magnitude)
+    fn message(&self) -> Option<String> {
+        match self.flow.phase {
+            Phase::Title => Some("CrasBall - press space to start".to_string()),
+            Phase::Playing => None,
+            Phase::Paused => Some("Paused - press P to resume".to_string()),
+            Phase::LevelComplete => Some(format!(
+                "Level complete! Score {} - press space to continue", self.flow.total_score
+            )),
+            Phase::GameOver => Some(format!(
+                "Game over! Score {} - press space", self.flow.total_score
+            ))
+        }
+    }
 }
 
 impl EventHandler for CrasballGame {
 
     fn mouse_button_down_event(
-        &mut self, ctx: &mut Context, button: MouseButton, _x: f32, _y: f32
+        &mut self, ctx: &mut Context, button: MouseButton, x: f32, y: f32
     ) {
-        if let MouseButton::Right = button {
-            if let CursorDirection::Vertical = self.cursor_direction {
-                self.cursor_direction = CursorDirection::Horizontal;
-                mouse::set_cursor_type(ctx, mouse::MouseCursor::NsResize);
-            } else {
-                self.cursor_direction = CursorDirection::Vertical;
-                mouse::set_cursor_type(ctx, mouse::MouseCursor::EwResize);
-            }
+        match button {
+            MouseButton::Left => {
+                let orientation = self.cursor_direction.orientation();
+
+                // Clicks outside the live areas, while a wall is still
+                // growing, or while not playing are ignored
+                self.flow.start_wall(Point2::new(x, y), orientation).ok();
+            },
+            MouseButton::Right => {
+                if let CursorDirection::Vertical = self.cursor_direction {
+                    self.cursor_direction = CursorDirection::Horizontal;
+                    mouse::set_cursor_type(ctx, mouse::MouseCursor::EwResize);
+                } else {
+                    self.cursor_direction = CursorDirection::Vertical;
+                    mouse::set_cursor_type(ctx, mouse::MouseCursor::NsResize);
+                }
+            },
+            _ => {}
         }
     }
 
+    fn key_down_event(&mut self, _ctx: &mut Context, keycode: KeyCode, _keymod: KeyMods, _repeat: bool) {
+        if keycode == KeyCode::R {
+            self.save_replay();
+            return;
+        }
+
+        let event = match (keycode, self.flow.phase) {
+            (KeyCode::Space, Phase::Title) => FlowEvent::Start,
+            (KeyCode::Space, Phase::LevelComplete) => FlowEvent::Continue,
+            (KeyCode::Space, Phase::GameOver) => FlowEvent::Continue,
+            (KeyCode::P, Phase::Playing) => FlowEvent::Pause,
+            (KeyCode::P, Phase::Paused) => FlowEvent::Resume,
+            (KeyCode::Escape, _) => FlowEvent::Quit,
+            _ => return
+        };
+
+        // Anything not allowed in the current phase is ignored
+        self.flow.handle(event).ok();
+    }
+
     fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
 
         while timer::check_update_time(ctx, DESIRED_FPS) {
             let delta = 1.0 / (DESIRED_FPS as f32);
-            self.state.update(delta);
+            self.flow.update(delta);
         }
 
         Ok(())
@@ -162,7 +167,21 @@ impl EventHandler for CrasballGame {
     fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
         graphics::clear(ctx, graphics::WHITE);
 
-        self.state.draw(ctx)?;
+        let mut commands = match &self.flow.game {
+            Some(state) => state.draw_list(),
+            None => Vec::new()
+        };
+
+        // Phase messages sit below the HUD
+        if let Some(message) = self.message() {
+            commands.push(DrawCommand::Text {
+                position: Point2::new(10.0, 30.0),
+                text: message,
+                colour: Colour::BLACK
+            });
+        }
+
+        render::draw(ctx, &commands)?;
 
         graphics::present(ctx)
     }
@@ -0,0 +1,70 @@
+use ggez::{Context, GameResult};
+use ggez::graphics::{self, Color};
+
+use crate::draw_list::{Colour, DrawCommand};
+use crate::game::GameState;
+
+fn color(colour: Colour) -> Color {
+    Color::new(colour.r, colour.g, colour.b, colour.a)
+}
+
+// Replays a draw list with ggez
+pub fn draw(ctx: &mut Context, commands: &[DrawCommand]) -> GameResult<()> {
+    for command in commands {
+        match command {
+            DrawCommand::Outline { points, colour } => {
+                let outline = graphics::Mesh::new_polygon(
+                    ctx,
+                    graphics::DrawMode::stroke(2.0),
+                    points.as_slice(),
+                    color(*colour),
+                )?;
+
+                graphics::draw(ctx, &outline, graphics::DrawParam::default())?;
+            },
+            DrawCommand::Rectangle { top_left, bottom_right, colour } => {
+                let rectangle = graphics::Mesh::new_rectangle(
+                    ctx,
+                    graphics::DrawMode::fill(),
+                    graphics::Rect {
+                        x: top_left.x,
+                        y: top_left.y,
+                        w: bottom_right.x - top_left.x,
+                        h: bottom_right.y - top_left.y
+                    },
+                    color(*colour),
+                )?;
+
+                graphics::draw(ctx, &rectangle, graphics::DrawParam::default())?;
+            },
+            DrawCommand::Circle { centre, radius, colour } => {
+                let circle = graphics::Mesh::new_circle(
+                    ctx,
+                    graphics::DrawMode::fill(),
+                    *centre,
+                    *radius,
+                    0.5,
+                    color(*colour),
+                )?;
+
+                graphics::draw(ctx, &circle, graphics::DrawParam::default())?;
+            },
+            DrawCommand::Text { position, text, colour } => {
+                let text = graphics::Text::new(text.as_str());
+                let param = graphics::DrawParam::default()
+                    .dest(*position)
+                    .color(color(*colour));
+
+                graphics::draw(ctx, &text, param)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+impl GameState {
+    pub fn draw(&self, ctx: &mut Context) -> GameResult<()> {
+        draw(ctx, &self.draw_list())
+    }
+}
@@ -0,0 +1,312 @@
+use nalgebra::{Point2};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use std::convert::TryInto;
+use std::error::Error;
+use std::fmt;
+
+use crate::broadphase::{Broadphase};
+use crate::game::{GameState, Orientation, Rectangle, Rules, TimeOutRule};
+use crate::scoring::{ScoreRules};
+
+// Replays always run at a fixed time step, the same as the game
+pub const FRAME_DELTA: f32 = 1.0 / 60.0;
+
+// Bump whenever the file layout or the simulation change in a way which
+// would make old replays play out differently. The determinism tests pin the
+// simulation against this
+pub const VERSION: u32 = 1;
+
+const MAGIC: &[u8; 4] = b"CRBR";
+
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub struct WallAction {
+    pub frame: u32,
+    pub click: Point2<f32>,
+    pub orientation: Orientation
+}
+
+// Everything needed to play a level again: the level is generated from the
+// seed and rules, and the only input is where walls were started
+#[derive(Clone,Debug,PartialEq)]
+pub struct Replay {
+    pub version: u32,
+    pub rules: Rules,
+    pub seed: u64,
+    pub level: u32,
+    pub playfield: Rectangle,
+    pub frames: u32,
+    pub actions: Vec<WallAction>,
+    pub final_hash: u64
+}
+
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub enum ReplayError {
+    NotAReplay,
+    Truncated,
+    UnknownVersion(u32),
+    UnknownOrientation(u8),
+    UnknownTimeOutRule(u8),
+    UnknownBroadphase(u8),
+    HashMismatch { expected: u64, actual: u64 }
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NotAReplay => write!(f, "not a replay file"),
+            Self::Truncated => write!(f, "replay file is truncated"),
+            Self::UnknownVersion(version) => write!(f, "unknown replay version {}", version),
+            Self::UnknownOrientation(byte) => write!(f, "unknown wall orientation {}", byte),
+            Self::UnknownTimeOutRule(byte) => write!(f, "unknown time out rule {}", byte),
+            Self::UnknownBroadphase(byte) => write!(f, "unknown broadphase {}", byte),
+            Self::HashMismatch { expected, actual } => write!(
+                f, "replay ended in state {:016x} but was recorded ending in {:016x}", actual, expected
+            )
+        }
+    }
+}
+
+impl Error for ReplayError {}
+
+// Reads little-endian values from the front of a slice
+struct Reader<'a> {
+    bytes: &'a [u8]
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], ReplayError> {
+        if self.bytes.len() < count {
+            return Err(ReplayError::Truncated);
+        }
+
+        let (taken, rest) = self.bytes.split_at(count);
+        self.bytes = rest;
+
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> Result<u8, ReplayError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn i16(&mut self) -> Result<i16, ReplayError> {
+        Ok(i16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, ReplayError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, ReplayError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn i64(&mut self) -> Result<i64, ReplayError> {
+        Ok(i64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn f32(&mut self) -> Result<f32, ReplayError> {
+        Ok(f32::from_bits(self.u32()?))
+    }
+
+    fn point(&mut self) -> Result<Point2<i16>, ReplayError> {
+        Ok(Point2::new(self.i16()?, self.i16()?))
+    }
+
+    fn rules(&mut self) -> Result<Rules, ReplayError> {
+        Ok(Rules {
+            clear_percent: self.i64()?,
+            base_ball_speed: self.f32()?,
+            ball_speed_per_level: self.f32()?,
+            wall_speed: self.f32()?,
+            base_time_limit: self.f32()?,
+            time_limit_per_level: self.f32()?,
+            on_time_out: match self.u8()? {
+                0 => TimeOutRule::LoseLife,
+                1 => TimeOutRule::GameOver,
+                other => return Err(ReplayError::UnknownTimeOutRule(other))
+            },
+            broadphase: match self.u8()? {
+                0 => Broadphase::BruteForce,
+                1 => Broadphase::Grid,
+                other => return Err(ReplayError::UnknownBroadphase(other))
+            },
+            scoring: ScoreRules {
+                points_per_cell: self.i64()?,
+                fill_bonus_per_percent: self.i64()?,
+                life_bonus: self.i64()?,
+                time_bonus_per_second: self.i64()?
+            }
+        })
+    }
+}
+
+fn write_rules(bytes: &mut Vec<u8>, rules: &Rules) {
+    bytes.extend_from_slice(&rules.clear_percent.to_le_bytes());
+
+    for v in [
+        rules.base_ball_speed,
+        rules.ball_speed_per_level,
+        rules.wall_speed,
+        rules.base_time_limit,
+        rules.time_limit_per_level
+    ].iter() {
+        bytes.extend_from_slice(&v.to_bits().to_le_bytes());
+    }
+
+    bytes.push(match rules.on_time_out {
+        TimeOutRule::LoseLife => 0,
+        TimeOutRule::GameOver => 1
+    });
+    bytes.push(match rules.broadphase {
+        Broadphase::BruteForce => 0,
+        Broadphase::Grid => 1
+    });
+
+    let scoring = &rules.scoring;
+
+    for v in [
+        scoring.points_per_cell,
+        scoring.fill_bonus_per_percent,
+        scoring.life_bonus,
+        scoring.time_bonus_per_second
+    ].iter() {
+        bytes.extend_from_slice(&v.to_le_bytes());
+    }
+}
+
+impl Replay {
+    pub fn new(rules: Rules, seed: u64, level: u32, playfield: Rectangle) -> Replay {
+        Replay {
+            version: VERSION,
+            rules,
+            seed,
+            level,
+            playfield,
+            frames: 0,
+            actions: Vec::new(),
+            final_hash: 0
+        }
+    }
+
+    // The level as it was before the first frame
+    pub fn start(&self) -> Result<GameState, ReplayError> {
+        if self.version != VERSION {
+            return Err(ReplayError::UnknownVersion(self.version));
+        }
+
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+
+        Ok(GameState::new_level(self.level, self.playfield, self.rules, &mut rng))
+    }
+
+    // Walls are started before the frame is updated, so call this before
+    // record_frame for the same frame
+    pub fn record_wall(&mut self, click: Point2<f32>, orientation: Orientation) {
+        self.actions.push(WallAction { frame: self.frames, click, orientation });
+    }
+
+    pub fn record_frame(&mut self, state: &GameState) {
+        self.frames += 1;
+        self.final_hash = state.state_hash();
+    }
+
+    // Plays the replay without a frontend, and checks it ends in exactly the
+    // state it was recorded ending in
+    pub fn play(&self) -> Result<GameState, ReplayError> {
+        let mut state = self.start()?;
+        let mut actions = self.actions.iter().peekable();
+
+        for frame in 0..self.frames {
+            while let Some(action) = actions.next_if(|a| a.frame == frame) {
+                state.start_wall(action.click, action.orientation).ok();
+            }
+
+            state.update(FRAME_DELTA);
+        }
+
+        let actual = state.state_hash();
+
+        if actual != self.final_hash {
+            return Err(ReplayError::HashMismatch { expected: self.final_hash, actual });
+        }
+
+        Ok(state)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+
+        bytes.extend_from_slice(&self.version.to_le_bytes());
+        write_rules(&mut bytes, &self.rules);
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        bytes.extend_from_slice(&self.level.to_le_bytes());
+
+        for p in [self.playfield.top_left, self.playfield.bottom_right].iter() {
+            bytes.extend_from_slice(&p.x.to_le_bytes());
+            bytes.extend_from_slice(&p.y.to_le_bytes());
+        }
+
+        bytes.extend_from_slice(&self.frames.to_le_bytes());
+        bytes.extend_from_slice(&self.final_hash.to_le_bytes());
+        bytes.extend_from_slice(&(self.actions.len() as u32).to_le_bytes());
+
+        for action in self.actions.iter() {
+            bytes.extend_from_slice(&action.frame.to_le_bytes());
+            bytes.extend_from_slice(&action.click.x.to_bits().to_le_bytes());
+            bytes.extend_from_slice(&action.click.y.to_bits().to_le_bytes());
+            bytes.push(match action.orientation {
+                Orientation::Horizontal => 0,
+                Orientation::Vertical => 1
+            });
+        }
+
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Replay, ReplayError> {
+        let mut reader = Reader { bytes };
+
+        if reader.take(MAGIC.len()).ok() != Some(&MAGIC[..]) {
+            return Err(ReplayError::NotAReplay);
+        }
+
+        // Older layouts can't be read at all
+        let version = reader.u32()?;
+
+        if version != VERSION {
+            return Err(ReplayError::UnknownVersion(version));
+        }
+
+        let mut replay = Replay {
+            version,
+            rules: reader.rules()?,
+            seed: reader.u64()?,
+            level: reader.u32()?,
+            playfield: Rectangle {
+                top_left: reader.point()?,
+                bottom_right: reader.point()?
+            },
+            frames: reader.u32()?,
+            final_hash: reader.u64()?,
+            actions: Vec::new()
+        };
+
+        let count = reader.u32()?;
+
+        for _ in 0..count {
+            let frame = reader.u32()?;
+            let click = Point2::new(reader.f32()?, reader.f32()?);
+            let orientation = match reader.u8()? {
+                0 => Orientation::Horizontal,
+                1 => Orientation::Vertical,
+                other => return Err(ReplayError::UnknownOrientation(other))
+            };
+
+            replay.actions.push(WallAction { frame, click, orientation });
+        }
+
+        Ok(replay)
+    }
+}
@@ -0,0 +1,62 @@
+use crate::game::{CapturedArea, GameState, WALL_THICKNESS};
+
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub struct ScoreRules {
+    pub points_per_cell: i64,
+    pub fill_bonus_per_percent: i64,
+    pub life_bonus: i64,
+    pub time_bonus_per_second: i64
+}
+
+impl Default for ScoreRules {
+    fn default() -> Self {
+        ScoreRules {
+            points_per_cell: 10,
+            fill_bonus_per_percent: 100,
+            life_bonus: 500,
+            time_bonus_per_second: 10
+        }
+    }
+}
+
+#[derive(Clone,Copy,Debug,Default,PartialEq)]
+pub struct ScoreBreakdown {
+    pub captured: i64,
+    pub fill_bonus: i64,
+    pub life_bonus: i64,
+    pub time_bonus: i64
+}
+
+impl ScoreBreakdown {
+    pub fn total(&self) -> i64 {
+        self.captured + self.fill_bonus + self.life_bonus + self.time_bonus
+    }
+}
+
+// Areas are measured in cells of the wall thickness grid, rounding down
+pub fn captured_points(rules: &ScoreRules, captured: &[CapturedArea]) -> i64 {
+    let cell_area = WALL_THICKNESS as i64 * WALL_THICKNESS as i64;
+
+    captured.iter().map(|c| c.area).sum::<i64>() * rules.points_per_cell / cell_area
+}
+
+// Adds the end of level bonuses to the points scored so far. The fill bonus is
+// for each whole percent captured past the clear threshold, and the time bonus
+// for each whole second left on the clock, if there is one.
+pub fn level_summary(state: &GameState) -> ScoreBreakdown {
+    let rules = &state.rules.scoring;
+    let seconds_remaining = if state.time_remaining.is_finite() {
+        state.time_remaining.max(0.0) as i64
+    } else {
+        0
+    };
+    let percent_captured = state.captured_area() * 100 / state.playfield_area.max(1);
+    let percent_over = (percent_captured - state.rules.clear_percent).max(0);
+
+    ScoreBreakdown {
+        fill_bonus: percent_over * rules.fill_bonus_per_percent,
+        life_bonus: state.lives as i64 * rules.life_bonus,
+        time_bonus: seconds_remaining * rules.time_bonus_per_second,
+        ..state.score
+    }
+}
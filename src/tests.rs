@@ -1,17 +1,55 @@
+mod ball_physics;
+mod broadphase;
+mod determinism;
+mod draw_list;
 mod fixture_parser;
-
-use nalgebra::{Point2};
+mod events;
+mod flow;
+mod game_state;
+mod growing_wall;
+mod live_area_normalisation;
+mod live_area_queries;
+mod live_area_validation;
+mod replay;
+mod rules;
+mod scoring;
+mod wall_placement;
+
+use nalgebra::{Point2, Vector2};
 
 use fixture_parser::{parse_live_area};
-use crate::game::{LiveArea};
+use crate::game::{Ball, CapturedArea, Edge, Facing, GameState, LiveArea, Rectangle, WallPlacementError};
 
-// does not add an edge that is within another edge
-// combines edges that share point and direction
 // NOTE edges should be INT
 
 
 // https://math.stackexchange.com/questions/15815/how-to-union-many-polygons-efficiently
 
+fn edge(a: (i16, i16), b: (i16, i16), n: Facing) -> Edge {
+    Edge {
+        a: Point2::new(a.0, a.1),
+        b: Point2::new(b.0, b.1),
+        n
+    }
+}
+
+fn playfield() -> Rectangle {
+    Rectangle {
+        top_left: Point2::new(0, 0),
+        bottom_right: Point2::new(800, 600)
+    }
+}
+
+fn ball(position: Point2<f32>, movement: Vector2<f32>) -> Ball {
+    Ball {
+        radius: 20.0,
+        position,
+        movement,
+        mass: None,
+        speed: None
+    }
+}
+
 fn simplest_live_area() -> LiveArea {
     parse_live_area("
 = = = = = = =
@@ -24,6 +62,32 @@ fn simplest_live_area() -> LiveArea {
 ")
 }
 
+fn l_shaped_live_area() -> LiveArea {
+    parse_live_area("
+      = = = =
+      =     =
+      =     =
+= = = =     =
+=           =
+=           =
+= = = = = = =
+")
+}
+
+fn simplest_game_state() -> GameState {
+    GameState::new(vec![
+        parse_live_area("
+= = = = = = =
+=           =
+=           =
+=           =
+=           =
+=     o     =
+= = = = = = =
+")
+    ])
+}
+
 #[test]
 fn test_add_partial_wall_to_down_facing_edge() {
 
@@ -34,7 +98,7 @@ fn test_add_partial_wall_to_down_facing_edge() {
         Point2::new(60, 0),
         Point2::new(60, 20),
         Point2::new(40, 20),
-    );
+    ).unwrap();
 
     let expected = vec![
         parse_live_area("
@@ -48,7 +112,8 @@ fn test_add_partial_wall_to_down_facing_edge() {
 ")
     ];
 
-    assert_eq!(output, expected);
+    assert_eq!(output.live_areas, expected);
+    assert_eq!(output.captured, []);
 
 }
 
@@ -62,7 +127,7 @@ fn test_add_partial_wall_to_left_facing_edge() {
         Point2::new(100, 40),
         Point2::new(100, 60),
         Point2::new(80, 60),
-    );
+    ).unwrap();
 
     let expected = vec![
         parse_live_area("
@@ -76,7 +141,7 @@ fn test_add_partial_wall_to_left_facing_edge() {
 ")
     ];
 
-    assert_eq!(output, expected);
+    assert_eq!(output.live_areas, expected);
 
 }
 
@@ -90,7 +155,7 @@ fn test_add_partial_wall_to_up_facing_edge() {
         Point2::new(60, 80),
         Point2::new(60, 100),
         Point2::new(40, 100),
-    );
+    ).unwrap();
 
     let expected = vec![
         parse_live_area("
@@ -104,7 +169,7 @@ fn test_add_partial_wall_to_up_facing_edge() {
 ")
     ];
 
-    assert_eq!(output, expected);
+    assert_eq!(output.live_areas, expected);
 
 }
 
@@ -118,7 +183,7 @@ fn test_wall_off_section() {
         Point2::new(80, 0),
         Point2::new(80, 100),
         Point2::new(60, 100),
-    );
+    ).unwrap();
 
     let expected = vec![
         parse_live_area("
@@ -132,7 +197,24 @@ fn test_wall_off_section() {
 ")
     ];
 
-    assert_eq!(output, expected);
+    assert_eq!(output.live_areas, expected);
+
+    let expected_captured = vec![
+        CapturedArea {
+            edges: parse_live_area("
+        = = =
+        =   =
+        =   =
+        =   =
+        =   =
+        =   =
+        = = =
+").edges,
+            area: 2000
+        }
+    ];
+
+    assert_eq!(output.captured, expected_captured);
 
 }
 
@@ -154,7 +236,7 @@ fn test_split_live_area_vertically() {
         Point2::new(60, 0),
         Point2::new(60, 100),
         Point2::new(40, 100),
-    );
+    ).unwrap();
 
     let expected = vec![
         parse_live_area("
@@ -177,7 +259,7 @@ fn test_split_live_area_vertically() {
 ")
     ];
 
-    assert_eq!(output, expected);
+    assert_eq!(output.live_areas, expected);
 
 }
 
@@ -199,7 +281,7 @@ fn test_split_live_area_horizontally() {
         Point2::new(100, 40),
         Point2::new(100, 60),
         Point2::new(0, 60)
-    );
+    ).unwrap();
 
     let expected = vec![
         parse_live_area("
@@ -219,7 +301,7 @@ fn test_split_live_area_horizontally() {
 ")
     ];
 
-    assert_eq!(output, expected);
+    assert_eq!(output.live_areas, expected);
 
 }
 
@@ -241,7 +323,7 @@ fn test_split_l_shaped_live_area() {
         Point2::new(60, 60),
         Point2::new(60, 100),
         Point2::new(40, 100)
-    );
+    ).unwrap();
 
     let expected = vec![
         parse_live_area("
@@ -264,7 +346,7 @@ fn test_split_l_shaped_live_area() {
 ")
     ];
 
-    assert_eq!(output, expected);
+    assert_eq!(output.live_areas, expected);
 
 }
 
@@ -286,7 +368,7 @@ fn test_add_wall_extending_existing_wall() {
         Point2::new(60, 60),
         Point2::new(60, 80),
         Point2::new(40, 80)
-    );
+    ).unwrap();
 
     let expected = vec![
         parse_live_area("
@@ -300,7 +382,7 @@ fn test_add_wall_extending_existing_wall() {
 ")
     ];
 
-    assert_eq!(output, expected);
+    assert_eq!(output.live_areas, expected);
 
 }
 
@@ -322,7 +404,7 @@ fn test_split_l_shaped_live_area_extending_existing_wall() {
         Point2::new(60, 60),
         Point2::new(60, 100),
         Point2::new(40, 100)
-    );
+    ).unwrap();
 
     let expected = vec![
         parse_live_area("
@@ -345,6 +427,194 @@ fn test_split_l_shaped_live_area_extending_existing_wall() {
 ")
     ];
 
-    assert_eq!(output, expected);
+    assert_eq!(output.live_areas, expected);
+
+}
+
+#[test]
+fn test_add_wall_crossing_vertex() {
+
+    let initial = parse_live_area("
+      = = = =
+      =   o =
+      =     =
+= = = =     =
+=           =
+= o         =
+= = = = = = =
+");
+
+    let output = initial.add_wall(
+        Point2::new(0, 60),
+        Point2::new(100, 60),
+        Point2::new(100, 80),
+        Point2::new(0, 80)
+    ).unwrap();
+
+    let expected = vec![
+        parse_live_area("
+
+
+
+
+= = = = = = =
+= o         =
+= = = = = = =
+"),
+        parse_live_area("
+      = = = =
+      =   o =
+      =     =
+      =     =
+      = = = =
+")
+    ];
+
+    assert_eq!(output.live_areas, expected);
+
+}
+
+#[test]
+fn test_add_wall_flush_with_concave_corner() {
+
+    let initial = parse_live_area("
+      = = = =
+      =   o =
+      =     =
+= = = =     =
+=           =
+= o         =
+= = = = = = =
+");
+
+    let output = initial.add_wall(
+        Point2::new(40, 60),
+        Point2::new(60, 60),
+        Point2::new(60, 80),
+        Point2::new(40, 80)
+    ).unwrap();
+
+    let expected = vec![
+        parse_live_area("
+      = = = =
+      =   o =
+      =     =
+= = = =     =
+=     =     =
+= o         =
+= = = = = = =
+")
+    ];
+
+    assert_eq!(output.live_areas, expected);
+
+}
+
+#[test]
+fn test_add_wall_touching_three_edges() {
+
+    let initial = parse_live_area("
+= = = = = = =
+= o   =   o =
+=     =     =
+=           =
+=           =
+=     o     =
+= = = = = = =
+");
+
+    let output = initial.add_wall(
+        Point2::new(0, 40),
+        Point2::new(100, 40),
+        Point2::new(100, 60),
+        Point2::new(0, 60)
+    ).unwrap();
+
+    let expected = vec![
+        parse_live_area("
+= = = =
+= o   =
+=     =
+= = = =
+"),
+        parse_live_area("
+
+
+
+= = = = = = =
+=           =
+=     o     =
+= = = = = = =
+"),
+        parse_live_area("
+      = = = =
+      =   o =
+      =     =
+      = = = =
+")
+    ];
+
+    assert_eq!(output.live_areas, expected);
+
+}
+
+#[test]
+fn test_add_wall_overlapping_outside_of_area() {
+
+    let initial = simplest_live_area();
+
+    let output = initial.add_wall(
+        Point2::new(40, -20),
+        Point2::new(60, -20),
+        Point2::new(60, 20),
+        Point2::new(40, 20),
+    ).unwrap();
+
+    let expected = vec![
+        parse_live_area("
+= = = = = = =
+=     =     =
+=           =
+=     o     =
+=           =
+=           =
+= = = = = = =
+")
+    ];
+
+    assert_eq!(output.live_areas, expected);
+
+}
+
+
+#[test]
+fn test_add_wall_enclosing_a_hole_is_rejected() {
+
+    let initial = simplest_live_area();
+
+    let output = initial.add_wall(
+        Point2::new(20, 20),
+        Point2::new(40, 20),
+        Point2::new(40, 40),
+        Point2::new(20, 40),
+    );
+
+    assert_eq!(output, Err(WallPlacementError::EnclosesHole));
+
+}
+
+#[test]
+fn test_add_wall_covering_a_ball_is_rejected() {
+
+    let initial = simplest_live_area();
+
+    let output = initial.add_wall(
+        Point2::new(0, 40),
+        Point2::new(100, 40),
+        Point2::new(100, 60),
+        Point2::new(0, 60),
+    );
+
+    assert_eq!(output, Err(WallPlacementError::CoversBall { ball: 0 }));
 
 }
@@ -0,0 +1,258 @@
+use nalgebra::{Point2, Vector2};
+
+use super::{simplest_live_area};
+use super::fixture_parser::{parse_live_area};
+use crate::game::{Ball, GameState, LiveArea};
+
+fn single_ball_state(position: Point2<f32>, movement: Vector2<f32>) -> GameState {
+    let mut live_area = simplest_live_area();
+    live_area.balls[0].position = position;
+    live_area.balls[0].movement = movement;
+
+    GameState::new(vec![live_area])
+}
+
+#[test]
+fn test_ball_bounces_at_time_of_impact() {
+
+    let mut state = single_ball_state(Point2::new(50.0, 50.0), Vector2::new(100.0, 0.0));
+
+    // Touches the right edge after 0.3, then travels back for the other 0.2
+    state.update(0.5);
+
+    let ball = state.live_areas[0].balls[0];
+
+    assert!((ball.position - Point2::new(60.0, 50.0)).norm() < 0.001);
+    assert_eq!(ball.movement, Vector2::new(-100.0, 0.0));
+
+}
+
+#[test]
+fn test_ball_bounces_out_of_a_corner() {
+
+    let mut state = single_ball_state(Point2::new(50.0, 50.0), Vector2::new(70.0, 70.0));
+
+    state.update(0.5);
+
+    let ball = state.live_areas[0].balls[0];
+
+    assert!(ball.movement.x < 0.0 && ball.movement.y < 0.0);
+    assert!(state.live_areas[0].ball_disc_is_inside(ball));
+
+}
+
+#[test]
+fn test_large_step_does_not_tunnel() {
+
+    let mut state = single_ball_state(Point2::new(50.0, 50.0), Vector2::new(80.0, 60.0));
+
+    state.update(3.0);
+
+    let ball = state.live_areas[0].balls[0];
+
+    assert!(state.live_areas[0].ball_disc_is_inside(ball));
+
+}
+
+#[test]
+fn test_ball_overlapping_an_edge_bounces_away() {
+
+    let mut state = single_ball_state(Point2::new(85.0, 50.0), Vector2::new(100.0, 0.0));
+
+    state.update(0.1);
+
+    let ball = state.live_areas[0].balls[0];
+
+    assert_eq!(ball.movement, Vector2::new(-100.0, 0.0));
+    assert!((ball.position - Point2::new(75.0, 50.0)).norm() < 0.001);
+
+}
+
+
+fn notched_live_area() -> LiveArea {
+    parse_live_area("
+          = = = = = =
+          =         =
+          =         =
+          =         =
+= = = = = =         =
+=                   =
+=                   =
+=                   =
+=                   =
+= = = = = = = = = = =
+")
+}
+
+#[test]
+fn test_ball_rolls_round_convex_corner() {
+
+    let mut live_area = notched_live_area();
+
+    // Just right of the notch's corner at (100, 80), heading straight up
+    live_area.balls.push(Ball {
+        radius: 20.0,
+        position: Point2::new(110.0, 140.0),
+        movement: Vector2::new(0.0, -100.0),
+        mass: None,
+        speed: None
+    });
+
+    let mut state = GameState::new(vec![live_area]);
+
+    state.update(0.5);
+
+    let ball = state.live_areas[0].balls[0];
+
+    // Hit where the centre is at 30 degrees from vertical to the corner
+    assert!((ball.movement - Vector2::new(86.6025, 50.0)).norm() < 0.01);
+    assert!(state.live_areas[0].ball_disc_is_inside(ball));
+
+}
+
+#[test]
+fn test_concave_corner_reflects_both_axes() {
+
+    let mut state = single_ball_state(Point2::new(50.0, 50.0), Vector2::new(60.0, 60.0));
+
+    // Reaches both the right and bottom edges at 0.5
+    state.update(0.5);
+
+    let ball = state.live_areas[0].balls[0];
+
+    assert!(ball.movement.x < 0.0);
+    assert_eq!(ball.movement.x, ball.movement.y);
+    assert!((ball.position - Point2::new(80.0, 80.0)).norm() < 0.001);
+
+}
+
+#[test]
+fn test_balls_with_a_set_speed_keep_it_through_collisions() {
+
+    let mut live_area = simplest_live_area();
+    live_area.balls[0].position = Point2::new(30.0, 50.0);
+    live_area.balls[0].movement = Vector2::new(30.0, 10.0);
+    live_area.balls[0].speed = Some(1000.0_f32.sqrt());
+    live_area.balls.push(Ball {
+        radius: 20.0,
+        position: Point2::new(70.0, 40.0),
+        movement: Vector2::new(-160.0, 120.0),
+        mass: None,
+        speed: Some(200.0)
+    });
+
+    let mut state = GameState::new(vec![live_area]);
+
+    for _ in 0..120 {
+        state.update(1.0 / 60.0);
+
+        let speeds: Vec<f32> = state.balls().map(|b| b.movement.norm()).collect();
+
+        assert!((speeds[0] - 31.6228).abs() < 0.01);
+        assert!((speeds[1] - 200.0).abs() < 0.01);
+    }
+
+}
+
+fn head_on_state(speed: Option<f32>) -> GameState {
+    let mut live_area = simplest_live_area();
+    live_area.balls[0].position = Point2::new(25.0, 50.0);
+    live_area.balls[0].movement = Vector2::new(100.0, 0.0);
+    live_area.balls[0].speed = speed;
+    live_area.balls.push(Ball {
+        radius: 20.0,
+        position: Point2::new(70.0, 50.0),
+        movement: Vector2::new(0.0, 0.0),
+        mass: None,
+        speed
+    });
+
+    GameState::new(vec![live_area])
+}
+
+#[test]
+fn test_equal_balls_exchange_momentum_head_on() {
+
+    let mut state = head_on_state(None);
+
+    // The gap of 5 has closed by the fourth step
+    for _ in 0..4 {
+        state.update(1.0 / 60.0);
+    }
+
+    let balls = &state.live_areas[0].balls;
+
+    assert_eq!(balls[0].movement, Vector2::new(0.0, 0.0));
+    assert_eq!(balls[1].movement, Vector2::new(100.0, 0.0));
+    assert!((balls[1].position - balls[0].position).norm() >= 40.0 - 0.001);
+
+}
+
+#[test]
+fn test_ball_with_a_set_speed_is_never_stopped() {
+
+    let mut state = head_on_state(Some(100.0));
+
+    for _ in 0..4 {
+        state.update(1.0 / 60.0);
+    }
+
+    let balls = &state.live_areas[0].balls;
+
+    assert_eq!(balls[0].movement, Vector2::new(-100.0, 0.0));
+    assert_eq!(balls[1].movement, Vector2::new(100.0, 0.0));
+    assert!(balls.iter().all(|b| state.live_areas[0].contains_point(b.position)));
+
+}
+
+#[test]
+fn test_heavy_ball_is_turned_less_than_light_ball() {
+
+    let mut live_area = simplest_live_area();
+    live_area.balls = vec![
+        Ball {
+            radius: 10.0,
+            position: Point2::new(30.0, 45.0),
+            movement: Vector2::new(100.0, 0.0),
+            mass: Some(100.0),
+            speed: None
+        },
+        Ball {
+            radius: 10.0,
+            position: Point2::new(70.0, 55.0),
+            movement: Vector2::new(-100.0, 0.0),
+            mass: Some(1.0),
+            speed: None
+        }
+    ];
+
+    let mut state = GameState::new(vec![live_area]);
+
+    for _ in 0..10 {
+        state.update(1.0 / 60.0);
+    }
+
+    let heavy = state.live_areas[0].balls[0];
+    let light = state.live_areas[0].balls[1];
+
+    assert!(heavy.movement.x > 0.0);
+    assert!(light.movement.x > 0.0);
+    assert!(heavy.movement.y.abs() < light.movement.y.abs());
+
+}
+
+#[test]
+fn test_mass_defaults_to_radius_squared() {
+
+    let ball = |radius, mass| Ball {
+        radius,
+        position: Point2::new(0.0, 0.0),
+        movement: Vector2::new(0.0, 0.0),
+        mass,
+        speed: None
+    };
+
+    assert_eq!(ball(20.0, None).mass(), 400.0);
+    assert_eq!(ball(20.0, Some(5.0)).mass(), 5.0);
+
+}
@@ -0,0 +1,77 @@
+use nalgebra::{Point2, Vector2};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use super::{ball, playfield};
+use crate::broadphase::{BallGrid, Broadphase};
+use crate::game::{Ball, GameState, Rectangle, Rules};
+
+fn crowded_game_state(broadphase: Broadphase) -> GameState {
+    let rules = Rules {
+        base_time_limit: 1000.0,
+        broadphase,
+        ..Rules::default()
+    };
+
+    let mut state = GameState::new_level(99, playfield(), rules, &mut ChaCha8Rng::seed_from_u64(22));
+    let wall = Rectangle {
+        top_left: Point2::new(400, 200),
+        bottom_right: Point2::new(420, 600)
+    };
+
+    // Walls can't be built on top of a ball
+    state.live_areas[0].balls.retain(|b| {
+        b.position.x <= 400.0 || b.position.x >= 420.0 || b.position.y <= 200.0
+    });
+    state.add_wall(wall).unwrap();
+
+    state
+}
+
+fn candidates(grid: &BallGrid, ball: &Ball) -> Vec<usize> {
+    let mut indices: Vec<usize> = grid.candidates(ball).collect();
+    indices.sort_unstable();
+
+    indices
+}
+
+#[test]
+fn test_grid_matches_brute_force() {
+
+    let mut brute_force = crowded_game_state(Broadphase::BruteForce);
+    let mut grid = crowded_game_state(Broadphase::Grid);
+
+    for _ in 0..200 {
+        brute_force.update(1.0 / 60.0);
+        grid.update(1.0 / 60.0);
+
+        assert_eq!(grid.live_areas, brute_force.live_areas);
+    }
+
+}
+
+#[test]
+fn test_grid_candidates_follow_moved_balls() {
+
+    let mut grid = BallGrid::new(Rectangle {
+        top_left: Point2::new(0, 0),
+        bottom_right: Point2::new(400, 400)
+    }, 40.0, 2);
+    let at = |x, y| ball(Point2::new(x, y), Vector2::new(0.0, 0.0));
+
+    grid.insert(0, &at(30.0, 30.0));
+    grid.insert(1, &at(300.0, 300.0));
+
+    assert_eq!(candidates(&grid, &at(60.0, 40.0)), vec![0]);
+
+    grid.update(1, &at(90.0, 30.0));
+
+    assert_eq!(candidates(&grid, &at(60.0, 40.0)), vec![0, 1]);
+    assert_eq!(candidates(&grid, &at(300.0, 300.0)), Vec::<usize>::new());
+
+    // Clamped to the border cells
+    grid.update(1, &at(-100.0, 300.0));
+
+    assert_eq!(candidates(&grid, &at(-90.0, 310.0)), vec![1]);
+
+}
@@ -0,0 +1,145 @@
+use nalgebra::{Point2};
+
+use super::{simplest_game_state};
+use super::fixture_parser::{parse_live_area};
+use crate::draw_list::{Colour, DrawCommand, GROWING_WALL_COLOURS};
+use crate::game::{CapturedArea, Orientation, Rectangle};
+
+#[test]
+fn test_balls_are_drawn_as_circles() {
+
+    let state = simplest_game_state();
+    let ball = state.balls().next().unwrap();
+
+    assert!(state.draw_list().contains(&DrawCommand::Circle {
+        centre: ball.position,
+        radius: ball.radius,
+        colour: Colour::RED
+    }));
+
+}
+
+#[test]
+fn test_captured_areas_and_walls_are_drawn() {
+
+    let mut state = simplest_game_state();
+
+    state.add_wall(Rectangle {
+        top_left: Point2::new(0, 40),
+        bottom_right: Point2::new(100, 60)
+    }).unwrap();
+
+    let commands = state.draw_list();
+
+    assert!(commands.contains(&DrawCommand::Rectangle {
+        top_left: Point2::new(0.0, 0.0),
+        bottom_right: Point2::new(100.0, 40.0),
+        colour: Colour::GREY
+    }));
+
+    assert!(commands.contains(&DrawCommand::Rectangle {
+        top_left: Point2::new(0.0, 40.0),
+        bottom_right: Point2::new(100.0, 60.0),
+        colour: Colour::BLACK
+    }));
+
+    assert!(commands.contains(&DrawCommand::Outline {
+        points: vec![
+            Point2::new(0.0, 60.0),
+            Point2::new(100.0, 60.0),
+            Point2::new(100.0, 100.0),
+            Point2::new(0.0, 100.0)
+        ],
+        colour: Colour::BLACK
+    }));
+
+}
+
+#[test]
+fn test_captured_area_decomposes_into_rectangles() {
+
+    let l_shape = parse_live_area("
+      = = = =
+      =     =
+      =     =
+= = = =     =
+=           =
+=           =
+= = = = = = =
+");
+
+    let captured = CapturedArea {
+        area: l_shape.area(),
+        edges: l_shape.edges
+    };
+
+    let rectangles = captured.rectangles();
+
+    assert_eq!(rectangles, vec![
+        Rectangle {
+            top_left: Point2::new(0, 60),
+            bottom_right: Point2::new(60, 100)
+        },
+        Rectangle {
+            top_left: Point2::new(60, 0),
+            bottom_right: Point2::new(100, 100)
+        }
+    ]);
+
+    assert_eq!(
+        rectangles.iter().map(|r| {
+            let size = r.bottom_right - r.top_left;
+            size.x as i64 * size.y as i64
+        }).sum::<i64>(),
+        captured.area
+    );
+
+}
+
+#[test]
+fn test_growing_wall_halves_have_their_own_colours() {
+
+    let mut state = simplest_game_state();
+
+    state.start_wall(Point2::new(50.0, 50.0), Orientation::Horizontal).unwrap();
+    state.update(0.05);
+
+    let colours: Vec<Colour> = state
+        .draw_list()
+        .iter()
+        .filter_map(|c| match c {
+            DrawCommand::Rectangle { colour, .. } => Some(*colour),
+            _ => None
+        })
+        .collect();
+
+    assert_eq!(colours, GROWING_WALL_COLOURS.to_vec());
+
+}
+
+#[test]
+fn test_hud_is_drawn_last() {
+
+    let mut state = simplest_game_state();
+    state.time_remaining = 79.5;
+
+    assert_eq!(state.draw_list().last(), Some(&DrawCommand::Text {
+        position: Point2::new(10.0, 10.0),
+        text: "Level 1  Lives 2  Time 80  Captured 0%  Score 0".to_string(),
+        colour: Colour::BLACK
+    }));
+
+}
+
+#[test]
+fn test_hud_leaves_out_time_without_a_limit() {
+
+    let state = simplest_game_state();
+
+    assert_eq!(state.draw_list().last(), Some(&DrawCommand::Text {
+        position: Point2::new(10.0, 10.0),
+        text: "Level 1  Lives 2  Captured 0%  Score 0".to_string(),
+        colour: Colour::BLACK
+    }));
+
+}
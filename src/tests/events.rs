@@ -0,0 +1,93 @@
+use nalgebra::{Point2, Vector2};
+
+use super::{ball, simplest_game_state, simplest_live_area};
+use super::fixture_parser::{parse_live_area};
+use crate::game::{GameEvent, GameState, LiveArea, Orientation, Rectangle};
+
+#[test]
+fn test_edge_hit_event() {
+
+    let mut live_area = simplest_live_area();
+    live_area.balls = vec![ball(Point2::new(50.0, 50.0), Vector2::new(100.0, 0.0))];
+
+    let mut state = GameState::new(vec![live_area]);
+
+    assert_eq!(state.update(0.5), vec![
+        GameEvent::BallHitEdge { area: 0, ball: 0, edge: 1, speed: 100.0 }
+    ]);
+
+}
+
+#[test]
+fn test_corner_hit_event() {
+
+    let live_area = parse_live_area("
+          = = = = = =
+          =         =
+          =         =
+          =         =
+= = = = = =         =
+=                   =
+=                   =
+=                   =
+=                   =
+= = = = = = = = = = =
+");
+
+    let mut state = GameState::new(vec![LiveArea {
+        balls: vec![ball(Point2::new(110.0, 140.0), Vector2::new(0.0, -100.0))],
+        ..live_area
+    }]);
+
+    match state.update(0.5)[..] {
+        [GameEvent::BallHitCorner { area: 0, ball: 0, corner, speed }] => {
+            assert_eq!(corner, Point2::new(100, 80));
+            assert!((speed - 86.6025).abs() < 0.01);
+        },
+        ref events => panic!("Unexpected events {:?}", events)
+    }
+
+}
+
+#[test]
+fn test_ball_hit_ball_event() {
+
+    let mut live_area = simplest_live_area();
+    live_area.balls = vec![
+        ball(Point2::new(25.0, 50.0), Vector2::new(100.0, 0.0)),
+        ball(Point2::new(75.0, 50.0), Vector2::new(-100.0, 0.0))
+    ];
+
+    let mut state = GameState::new(vec![live_area]);
+
+    assert_eq!(state.update(0.1), vec![
+        GameEvent::BallHitBall { area: 0, first: 0, second: 1, speed: 200.0 }
+    ]);
+
+}
+
+#[test]
+fn test_growing_wall_events() {
+
+    let mut state = simplest_game_state();
+
+    // The ball sits in the bottom row, so the half growing down is destroyed
+    state.start_wall(Point2::new(50.0, 30.0), Orientation::Vertical).unwrap();
+
+    let events: Vec<GameEvent> = (0..60)
+        .flat_map(|_| state.update(1.0 / 60.0))
+        .filter(|e| matches!(e, GameEvent::WallCompleted { .. } | GameEvent::WallDestroyed { .. }))
+        .collect();
+
+    assert_eq!(events, vec![
+        GameEvent::WallDestroyed { half: 1, area: 0, ball: 0 },
+        GameEvent::WallCompleted {
+            half: 0,
+            wall: Rectangle {
+                top_left: Point2::new(40, 0),
+                bottom_right: Point2::new(60, 40)
+            }
+        }
+    ]);
+
+}
@@ -103,7 +103,9 @@ fn find_balls(grid: &SymbolGrid) -> Vec<Ball> {
                         cell_i as f32 * 20.0 - 10.0,
                         row_i as f32 * 20.0 - 10.0,
                     ),
-                    movement: Vector2::new(0.0, 0.0)
+                    movement: Vector2::new(0.0, 0.0),
+                    mass: None,
+                    speed: None
                 });
 
             }
@@ -128,12 +130,16 @@ fn test_find_balls() {
         Ball {
             radius: 20.0,
             position: Point2::new(10.0, 10.0),
-            movement: Vector2::new(0.0, 0.0)
+            movement: Vector2::new(0.0, 0.0),
+            mass: None,
+            speed: None
         },
         Ball {
             radius: 20.0,
             position: Point2::new(30.0, 30.0),
-            movement: Vector2::new(0.0, 0.0)
+            movement: Vector2::new(0.0, 0.0),
+            mass: None,
+            speed: None
         }
     ]);
 
@@ -0,0 +1,160 @@
+use nalgebra::{Point2};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use super::{playfield};
+use crate::flow::{Flow, FlowEvent, FlowHooks, Phase, TransitionError};
+use crate::game::{GameState, Orientation, Rules, WallPlacementError};
+
+fn new_flow(rules: Rules) -> Flow {
+    Flow::new(playfield(), rules, 0)
+}
+
+struct RecordingHooks {
+    log: Rc<RefCell<Vec<String>>>
+}
+
+impl FlowHooks for RecordingHooks {
+    fn on_enter(&mut self, phase: Phase, game: Option<&GameState>) {
+        self.log.borrow_mut().push(format!("enter {:?} {:?}", phase, game.map(|g| g.level)));
+    }
+
+    fn on_exit(&mut self, phase: Phase, _game: Option<&GameState>) {
+        self.log.borrow_mut().push(format!("exit {:?}", phase));
+    }
+}
+
+#[test]
+fn test_start_creates_first_level() {
+
+    let mut flow = new_flow(Rules::default());
+
+    assert_eq!(flow.phase, Phase::Title);
+    assert!(flow.game.is_none());
+
+    flow.handle(FlowEvent::Start).unwrap();
+
+    assert_eq!(flow.phase, Phase::Playing);
+    assert_eq!(flow.game.as_ref().map(|g| g.level), Some(1));
+
+}
+
+#[test]
+fn test_pause_stops_the_game() {
+
+    let mut flow = new_flow(Rules::default());
+    flow.handle(FlowEvent::Start).unwrap();
+    flow.handle(FlowEvent::Pause).unwrap();
+
+    let time_remaining = flow.game.as_ref().unwrap().time_remaining;
+    flow.update(1.0);
+
+    assert_eq!(flow.phase, Phase::Paused);
+    assert_eq!(flow.game.as_ref().unwrap().time_remaining, time_remaining);
+
+    flow.handle(FlowEvent::Resume).unwrap();
+    flow.update(1.0);
+
+    assert_eq!(flow.phase, Phase::Playing);
+    assert_eq!(flow.game.as_ref().unwrap().time_remaining, time_remaining - 1.0);
+
+}
+
+#[test]
+fn test_invalid_transition() {
+
+    let mut flow = new_flow(Rules::default());
+
+    assert_eq!(
+        flow.handle(FlowEvent::Pause),
+        Err(TransitionError { phase: Phase::Title, event: FlowEvent::Pause })
+    );
+    assert_eq!(flow.phase, Phase::Title);
+
+}
+
+#[test]
+fn test_walls_can_only_be_started_while_playing() {
+
+    let mut flow = new_flow(Rules::default());
+    let click = Point2::new(410.0, 300.0);
+
+    assert_eq!(flow.start_wall(click, Orientation::Vertical), Err(WallPlacementError::NotPlaying));
+
+    flow.handle(FlowEvent::Start).unwrap();
+    flow.handle(FlowEvent::Pause).unwrap();
+
+    assert_eq!(flow.start_wall(click, Orientation::Vertical), Err(WallPlacementError::NotPlaying));
+    assert!(flow.game.as_ref().unwrap().growing_wall.is_none());
+
+    flow.handle(FlowEvent::Resume).unwrap();
+
+    assert_eq!(flow.start_wall(click, Orientation::Vertical), Ok(()));
+    assert!(flow.game.as_ref().unwrap().growing_wall.is_some());
+
+}
+
+#[test]
+fn test_level_complete_moves_on_to_next_level() {
+
+    let mut flow = new_flow(Rules {
+        clear_percent: 0,
+        ..Rules::default()
+    });
+
+    flow.handle(FlowEvent::Start).unwrap();
+    flow.update(0.5);
+
+    assert_eq!(flow.phase, Phase::LevelComplete);
+    assert_eq!(flow.level_summary.map(|s| s.total()), Some(flow.total_score));
+    assert!(flow.total_score > 0);
+
+    flow.handle(FlowEvent::Continue).unwrap();
+
+    assert_eq!(flow.phase, Phase::Playing);
+    assert_eq!(flow.game.as_ref().map(|g| g.level), Some(2));
+    assert_eq!(flow.level_summary, None);
+
+}
+
+#[test]
+fn test_game_over_returns_to_title() {
+
+    let mut flow = new_flow(Rules::default());
+
+    flow.handle(FlowEvent::Start).unwrap();
+    flow.game.as_mut().unwrap().lives = 0;
+    flow.update(0.5);
+
+    assert_eq!(flow.phase, Phase::GameOver);
+
+    flow.handle(FlowEvent::Continue).unwrap();
+
+    assert_eq!(flow.phase, Phase::Title);
+    assert!(flow.game.is_none());
+    assert_eq!(flow.total_score, 0);
+
+}
+
+#[test]
+fn test_hooks_on_enter_and_exit() {
+
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let mut flow = new_flow(Rules::default());
+
+    flow.add_hooks(Box::new(RecordingHooks { log: log.clone() }));
+
+    flow.handle(FlowEvent::Start).unwrap();
+    flow.handle(FlowEvent::Pause).unwrap();
+    flow.handle(FlowEvent::Quit).unwrap();
+
+    assert_eq!(*log.borrow(), vec![
+        "exit Title",
+        "enter Playing Some(1)",
+        "exit Playing",
+        "enter Paused Some(1)",
+        "exit Paused",
+        "enter Title None"
+    ]);
+
+}
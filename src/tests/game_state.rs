@@ -0,0 +1,110 @@
+use nalgebra::{Point2, Vector2};
+
+use super::fixture_parser::{parse_live_area};
+use crate::game::{GameState, LiveArea, Rectangle};
+
+fn split_live_areas() -> Vec<LiveArea> {
+    vec![
+        parse_live_area("
+= = = =
+=     =
+=     =
+= o   =
+=     =
+=     =
+= = = =
+"),
+        parse_live_area("
+      = = = =
+      =     =
+      =     =
+      =     =
+      =     =
+      =   o =
+      = = = =
+")
+    ]
+}
+
+#[test]
+fn test_add_wall_splits_the_live_area_it_crosses() {
+
+    let mut state = GameState::new(split_live_areas());
+
+    state.add_wall(Rectangle {
+        top_left: Point2::new(60, 40),
+        bottom_right: Point2::new(100, 60)
+    }).unwrap();
+
+    assert_eq!(state.live_areas, vec![
+        split_live_areas().remove(0),
+        parse_live_area("
+
+
+
+      = = = =
+      =     =
+      =   o =
+      = = = =
+")
+    ]);
+
+    assert_eq!(state.captured.len(), 1);
+    assert_eq!(state.captured[0].area, 40 * 40);
+
+}
+
+#[test]
+fn test_balls_stay_in_their_live_area() {
+
+    let mut live_areas = vec![
+        parse_live_area("
+= = = = = = =
+=           =
+=           =
+=     o     =
+=           =
+=           =
+= = = = = = =
+"),
+        parse_live_area("
+            = = = = = = =
+            =           =
+            =           =
+            =     o     =
+            =           =
+            =           =
+            = = = = = = =
+")
+    ];
+
+    live_areas[0].balls[0].movement = Vector2::new(100.0, 0.0);
+    live_areas[1].balls[0].movement = Vector2::new(-100.0, 0.0);
+
+    let mut state = GameState::new(live_areas);
+
+    for _ in 0..60 {
+        state.update(1.0 / 60.0);
+
+        assert_eq!(state.live_areas[0].balls.len(), 1);
+        assert_eq!(state.live_areas[1].balls.len(), 1);
+        assert!(state.live_areas[0].ball_disc_is_inside(state.live_areas[0].balls[0]));
+        assert!(state.live_areas[1].ball_disc_is_inside(state.live_areas[1].balls[0]));
+    }
+
+}
+
+#[test]
+fn test_ball_outside_its_live_area_is_moved() {
+
+    let mut live_areas = split_live_areas();
+    live_areas[0].balls[0].position = Point2::new(80.0, 20.0);
+
+    let mut state = GameState::new(live_areas);
+
+    state.update(0.0);
+
+    assert_eq!(state.live_areas[0].balls.len(), 0);
+    assert_eq!(state.live_areas[1].balls.len(), 2);
+
+}
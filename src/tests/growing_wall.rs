@@ -0,0 +1,217 @@
+use nalgebra::{Point2};
+
+use super::fixture_parser::{parse_live_area};
+use crate::game::{GrowingWall, LiveArea, Orientation, Rectangle, WallHalfState};
+
+fn vertical_wall() -> GrowingWall {
+    GrowingWall::new(
+        Rectangle {
+            top_left: Point2::new(40, 0),
+            bottom_right: Point2::new(60, 100)
+        },
+        Point2::new(50.0, 45.0),
+        Orientation::Vertical,
+        40.0
+    )
+}
+
+#[test]
+fn test_halves_split_at_nearest_grid_line() {
+
+    let wall = vertical_wall();
+
+    assert_eq!(wall.halves[0].target, Rectangle {
+        top_left: Point2::new(40, 0),
+        bottom_right: Point2::new(60, 40)
+    });
+
+    assert_eq!(wall.halves[1].target, Rectangle {
+        top_left: Point2::new(40, 40),
+        bottom_right: Point2::new(60, 100)
+    });
+
+}
+
+#[test]
+fn test_halves_grow_from_click_point() {
+
+    let mut wall = vertical_wall();
+    let mut live_areas: Vec<LiveArea> = Vec::new();
+
+    wall.update(0.5, &mut live_areas);
+
+    assert_eq!(wall.halves[0].built(), (Point2::new(40.0, 20.0), Point2::new(60.0, 40.0)));
+    assert_eq!(wall.halves[1].built(), (Point2::new(40.0, 40.0), Point2::new(60.0, 60.0)));
+
+}
+
+#[test]
+fn test_each_half_is_added_when_complete() {
+
+    let mut wall = vertical_wall();
+    let mut live_areas = vec![
+        parse_live_area("
+= = = = = = =
+=           =
+=           =
+= o       o =
+=           =
+=           =
+= = = = = = =
+")
+    ];
+
+    wall.update(1.0, &mut live_areas);
+
+    assert_eq!(wall.halves[0].state, WallHalfState::Complete);
+    assert_eq!(wall.halves[1].state, WallHalfState::Growing);
+    assert_eq!(live_areas, vec![
+        parse_live_area("
+= = = = = = =
+=     =     =
+=     =     =
+= o       o =
+=           =
+=           =
+= = = = = = =
+")
+    ]);
+
+    let update = wall.update(0.5, &mut live_areas);
+
+    assert!(wall.is_finished());
+    assert_eq!(update.lives_lost, 0);
+    assert_eq!(live_areas, vec![
+        parse_live_area("
+= = = =
+=     =
+=     =
+= o   =
+=     =
+=     =
+= = = =
+"),
+        parse_live_area("
+      = = = =
+      =     =
+      =     =
+      =   o =
+      =     =
+      =     =
+      = = = =
+")
+    ]);
+
+}
+
+#[test]
+fn test_ball_touching_half_destroys_it() {
+
+    let mut wall = vertical_wall();
+    let mut live_areas = vec![
+        parse_live_area("
+= = = = = = =
+=           =
+=           =
+=           =
+=           =
+=     o     =
+= = = = = = =
+")
+    ];
+
+    let mut lives_lost = 0;
+
+    for _ in 0..4 {
+        lives_lost += wall.update(0.25, &mut live_areas).lives_lost;
+    }
+
+    assert_eq!(lives_lost, 1);
+    assert_eq!(wall.halves[0].state, WallHalfState::Complete);
+    assert_eq!(wall.halves[1].state, WallHalfState::Destroyed);
+    assert_eq!(live_areas, vec![
+        parse_live_area("
+= = = = = = =
+=     =     =
+=     =     =
+=           =
+=           =
+=     o     =
+= = = = = = =
+")
+    ]);
+
+}
+
+#[test]
+fn test_ball_touching_last_piece_destroys_half() {
+
+    let mut wall = vertical_wall();
+    let mut live_areas = vec![
+        parse_live_area("
+= = = = = = =
+=         o =
+=           =
+=           =
+=           =
+=           =
+= = = = = = =
+")
+    ];
+
+    // Clear of the first half until its final step reaches the top
+    live_areas[0].balls[0].position = Point2::new(79.0, 4.0);
+
+    assert_eq!(wall.update(0.5, &mut live_areas).lives_lost, 0);
+
+    let update = wall.update(0.5, &mut live_areas);
+
+    assert_eq!(update.lives_lost, 1);
+    assert_eq!(update.walls, []);
+    assert_eq!(wall.halves[0].state, WallHalfState::Destroyed);
+    assert_eq!(live_areas.len(), 1);
+    assert_eq!(live_areas[0].balls.len(), 1);
+
+}
+
+#[test]
+fn test_click_next_to_an_end_only_grows_one_half() {
+
+    let mut wall = GrowingWall::new(
+        Rectangle {
+            top_left: Point2::new(40, 0),
+            bottom_right: Point2::new(60, 100)
+        },
+        Point2::new(50.0, 5.0),
+        Orientation::Vertical,
+        40.0
+    );
+    let mut live_areas = vec![
+        parse_live_area("
+= = = = = = =
+=           =
+=           =
+= o         =
+=           =
+=           =
+= = = = = = =
+")
+    ];
+
+    assert_eq!(wall.halves[0].state, WallHalfState::Complete);
+    assert_eq!(wall.halves[1].state, WallHalfState::Growing);
+
+    let update = wall.update(0.5, &mut live_areas);
+
+    assert_eq!(update.walls, []);
+    assert_eq!(update.events, []);
+
+    let update = wall.update(2.0, &mut live_areas);
+
+    assert!(wall.is_finished());
+    assert_eq!(update.walls, vec![Rectangle {
+        top_left: Point2::new(40, 0),
+        bottom_right: Point2::new(60, 100)
+    }]);
+
+}
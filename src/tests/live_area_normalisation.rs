@@ -0,0 +1,103 @@
+use super::{edge};
+use super::fixture_parser::{parse_live_area};
+use crate::game::{Facing, LiveArea};
+
+fn empty_square_live_area() -> LiveArea {
+    parse_live_area("
+= = = = = = =
+=           =
+=           =
+=           =
+=           =
+=           =
+= = = = = = =
+")
+}
+
+#[test]
+fn test_combines_edges_that_share_point_and_direction() {
+
+    let live_area = LiveArea {
+        balls: Vec::new(),
+        edges: vec![
+            edge((0, 0), (40, 0), Facing::Down),
+            edge((40, 0), (100, 0), Facing::Down),
+            edge((100, 0), (100, 100), Facing::Left),
+            edge((100, 100), (0, 100), Facing::Up),
+            edge((0, 100), (0, 60), Facing::Right),
+            edge((0, 60), (0, 0), Facing::Right)
+        ]
+    };
+
+    assert_eq!(live_area.normalised(), empty_square_live_area());
+
+}
+
+#[test]
+fn test_drops_zero_length_edges() {
+
+    let live_area = LiveArea {
+        balls: Vec::new(),
+        edges: vec![
+            edge((0, 0), (100, 0), Facing::Down),
+            edge((100, 0), (100, 0), Facing::Left),
+            edge((100, 0), (100, 100), Facing::Left),
+            edge((100, 100), (0, 100), Facing::Up),
+            edge((0, 100), (0, 0), Facing::Right)
+        ]
+    };
+
+    assert_eq!(live_area.normalised(), empty_square_live_area());
+
+}
+
+#[test]
+fn test_does_not_add_an_edge_that_is_within_another_edge() {
+
+    let live_area = LiveArea {
+        balls: Vec::new(),
+        edges: vec![
+            edge((0, 0), (100, 0), Facing::Down),
+            edge((100, 0), (100, 100), Facing::Left),
+            edge((100, 100), (0, 100), Facing::Up),
+            edge((0, 100), (0, 20), Facing::Right),
+            edge((0, 20), (0, 60), Facing::Left),
+            edge((0, 60), (0, 0), Facing::Right)
+        ]
+    };
+
+    assert_eq!(live_area.normalised(), empty_square_live_area());
+
+}
+
+#[test]
+fn test_starts_from_left_most_top_most_point() {
+
+    let live_area = LiveArea {
+        balls: Vec::new(),
+        edges: vec![
+            edge((100, 100), (0, 100), Facing::Up),
+            edge((0, 100), (0, 0), Facing::Right),
+            edge((0, 0), (100, 0), Facing::Down),
+            edge((100, 0), (100, 100), Facing::Left)
+        ]
+    };
+
+    assert_eq!(live_area.normalised(), empty_square_live_area());
+
+}
+
+#[test]
+fn test_new_normalises_edges() {
+
+    let edges = vec![
+        edge((100, 0), (100, 60), Facing::Left),
+        edge((100, 60), (100, 100), Facing::Left),
+        edge((100, 100), (0, 100), Facing::Up),
+        edge((0, 100), (0, 0), Facing::Right),
+        edge((0, 0), (100, 0), Facing::Down)
+    ];
+
+    assert_eq!(LiveArea::new(Vec::new(), edges), Ok(empty_square_live_area()));
+
+}
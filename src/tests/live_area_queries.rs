@@ -0,0 +1,115 @@
+use nalgebra::{Point2, Vector2};
+
+use super::{l_shaped_live_area};
+use super::fixture_parser::{parse_live_area};
+use crate::game::{Ball, Edge, Facing, LiveArea, Rectangle};
+
+#[test]
+fn test_area_of_rectangle() {
+
+    let live_area = parse_live_area("
+= = = = =
+=       =
+=       =
+= = = = =
+");
+
+    assert_eq!(live_area.area(), 60 * 40);
+
+}
+
+#[test]
+fn test_area_of_l_shape() {
+
+    assert_eq!(l_shaped_live_area().area(), 40 * 60 + 100 * 40);
+
+}
+
+#[test]
+fn test_perimeter_of_l_shape() {
+
+    assert_eq!(l_shaped_live_area().perimeter(), 400);
+
+}
+
+#[test]
+fn test_bounding_box_of_l_shape() {
+
+    assert_eq!(l_shaped_live_area().bounding_box(), Rectangle {
+        top_left: Point2::new(0, 0),
+        bottom_right: Point2::new(100, 100)
+    });
+
+}
+
+#[test]
+fn test_centroid_of_l_shape() {
+
+    // Upper arm 2400 centred on (80, 30), lower arm 4000 centred on (50, 80)
+    let expected = Point2::new(
+        (2400.0 * 80.0 + 4000.0 * 50.0) / 6400.0,
+        (2400.0 * 30.0 + 4000.0 * 80.0) / 6400.0
+    );
+
+    assert_eq!(l_shaped_live_area().centroid(), expected);
+
+}
+
+#[test]
+fn test_contains_point_on_large_board() {
+
+    let live_area = LiveArea::new(Vec::new(), vec![
+        Edge { a: Point2::new(0, 0), b: Point2::new(3000, 0), n: Facing::Down },
+        Edge { a: Point2::new(3000, 0), b: Point2::new(3000, 2000), n: Facing::Left },
+        Edge { a: Point2::new(3000, 2000), b: Point2::new(0, 2000), n: Facing::Up },
+        Edge { a: Point2::new(0, 2000), b: Point2::new(0, 0), n: Facing::Right }
+    ]).unwrap();
+
+    assert!(live_area.contains_point(Point2::new(100.0, 1500.0)));
+    assert!(!live_area.contains_point(Point2::new(3100.0, 1500.0)));
+
+}
+
+#[test]
+fn test_contains_point_with_ray_through_vertex() {
+
+    let live_area = l_shaped_live_area();
+
+    // Rays to the right pass exactly through the corners along y = 0 and y = 60
+    assert!(!live_area.contains_point(Point2::new(-10.0, 0.0)));
+    assert!(!live_area.contains_point(Point2::new(-10.0, 60.0)));
+    assert!(live_area.contains_point(Point2::new(70.0, 60.0)));
+
+}
+
+#[test]
+fn test_contains_point_on_boundary() {
+
+    let live_area = l_shaped_live_area();
+
+    assert!(live_area.contains_point(Point2::new(30.0, 60.0)));
+    assert!(live_area.contains_point(Point2::new(60.0, 60.0)));
+    assert!(live_area.contains_point(Point2::new(100.0, 0.0)));
+    assert!(!live_area.contains_point(Point2::new(110.0, 0.0)));
+
+}
+
+#[test]
+fn test_ball_disc_is_inside() {
+
+    let live_area = l_shaped_live_area();
+
+    let ball = |x, y| Ball {
+        radius: 20.0,
+        position: Point2::new(x, y),
+        movement: Vector2::new(0.0, 0.0),
+        mass: None,
+        speed: None
+    };
+
+    assert!(live_area.ball_disc_is_inside(ball(80.0, 20.0)));
+    assert!(live_area.ball_disc_is_inside(ball(20.0, 80.0)));
+    assert!(!live_area.ball_disc_is_inside(ball(50.0, 70.0)));
+    assert!(!live_area.ball_disc_is_inside(ball(10.0, 80.0)));
+
+}
@@ -0,0 +1,142 @@
+use nalgebra::{Point2};
+
+use super::{edge};
+use super::fixture_parser::{parse_live_area};
+use crate::game::{Edge, Facing, LiveArea, LiveAreaError};
+
+fn square_edges() -> Vec<Edge> {
+    vec![
+        edge((0, 0), (100, 0), Facing::Down),
+        edge((100, 0), (100, 100), Facing::Left),
+        edge((100, 100), (0, 100), Facing::Up),
+        edge((0, 100), (0, 0), Facing::Right)
+    ]
+}
+
+#[test]
+fn test_new_accepts_valid_edges() {
+
+    let live_area = LiveArea::new(Vec::new(), square_edges());
+
+    assert_eq!(live_area.map(|a| a.edges), Ok(square_edges()));
+
+}
+
+#[test]
+fn test_fixtures_are_valid() {
+
+    let live_area = parse_live_area("
+= = = = = = =
+=     =     =
+=   = = =   =
+=   = =     =
+=     =     =
+=           =
+= = = = = = =
+");
+
+    assert!(live_area.is_valid());
+
+}
+
+#[test]
+fn test_add_wall_output_is_valid() {
+
+    let initial = parse_live_area("
+      = = = =
+      =   o =
+      =     =
+= = = =     =
+=           =
+= o         =
+= = = = = = =
+");
+
+    // Only touches the concave corner so the outline meets itself there
+    let output = initial.add_wall(
+        Point2::new(60, 60),
+        Point2::new(80, 60),
+        Point2::new(80, 80),
+        Point2::new(60, 80)
+    ).unwrap();
+
+    assert!(output.live_areas[0].is_valid());
+
+}
+
+#[test]
+fn test_too_few_edges() {
+
+    let edges = square_edges()[0..3].to_vec();
+
+    assert_eq!(LiveArea::new(Vec::new(), edges), Err(LiveAreaError::TooFewEdges));
+
+}
+
+#[test]
+fn test_open_loop() {
+
+    let mut edges = square_edges();
+    edges[2].b = Point2::new(20, 100);
+
+    assert_eq!(LiveArea::new(Vec::new(), edges), Err(LiveAreaError::OpenLoop { edge: 2 }));
+
+}
+
+#[test]
+fn test_diagonal_edge() {
+
+    let edges = vec![
+        edge((0, 0), (100, 0), Facing::Down),
+        edge((100, 0), (100, 100), Facing::Left),
+        edge((100, 100), (0, 80), Facing::Up),
+        edge((0, 80), (0, 0), Facing::Right)
+    ];
+
+    assert_eq!(LiveArea::new(Vec::new(), edges), Err(LiveAreaError::DiagonalEdge { edge: 2 }));
+
+}
+
+#[test]
+fn test_wrong_winding() {
+
+    let edges = vec![
+        edge((0, 0), (0, 100), Facing::Right),
+        edge((0, 100), (100, 100), Facing::Up),
+        edge((100, 100), (100, 0), Facing::Left),
+        edge((100, 0), (0, 0), Facing::Down)
+    ];
+
+    assert_eq!(LiveArea::new(Vec::new(), edges), Err(LiveAreaError::WrongWinding));
+
+}
+
+#[test]
+fn test_inconsistent_facing() {
+
+    let mut edges = square_edges();
+    edges[1].n = Facing::Right;
+
+    assert_eq!(LiveArea::new(Vec::new(), edges), Err(LiveAreaError::InconsistentFacing { edge: 1 }));
+
+}
+
+#[test]
+fn test_self_intersection() {
+
+    // A bow tie made of two squares, crossing where they join
+    let edges = vec![
+        edge((0, 0), (60, 0), Facing::Down),
+        edge((60, 0), (60, 100), Facing::Left),
+        edge((60, 100), (100, 100), Facing::Down),
+        edge((100, 100), (100, 40), Facing::Right),
+        edge((100, 40), (0, 40), Facing::Up),
+        edge((0, 40), (0, 0), Facing::Right)
+    ];
+
+    assert_eq!(
+        LiveArea::new(Vec::new(), edges),
+        Err(LiveAreaError::SelfIntersection { first: 1, second: 4 })
+    );
+
+}
@@ -0,0 +1,116 @@
+use nalgebra::{Point2};
+
+use super::{playfield};
+use crate::flow::{Flow, FlowEvent};
+use crate::game::{Orientation, Rules, TimeOutRule};
+use crate::replay::{Replay, ReplayError, WallAction, VERSION};
+
+// Plays part of a level through the flow, as the frontend would
+fn recorded_replay(rules: Rules) -> (Replay, u64) {
+    let mut flow = Flow::new(playfield(), rules, 25);
+
+    flow.handle(FlowEvent::Start).unwrap();
+
+    for frame in 0..600 {
+        if frame == 30 {
+            flow.start_wall(Point2::new(410.0, 300.0), Orientation::Vertical).ok();
+        }
+
+        if frame == 300 {
+            flow.start_wall(Point2::new(200.0, 150.0), Orientation::Horizontal).ok();
+        }
+
+        flow.update(1.0 / 60.0);
+    }
+
+    let hash = flow.game.as_ref().unwrap().state_hash();
+
+    (flow.replay.unwrap(), hash)
+}
+
+#[test]
+fn test_recorded_replay_plays_back_to_the_same_state() {
+
+    let (replay, hash) = recorded_replay(Rules::default());
+
+    assert_eq!(replay.version, VERSION);
+    assert_eq!(replay.level, 1);
+    assert_eq!(replay.frames, 600);
+    assert_eq!(replay.actions.len(), 2);
+    assert_eq!(replay.final_hash, hash);
+
+    assert_eq!(replay.play().map(|state| state.state_hash()), Ok(hash));
+
+}
+
+#[test]
+fn test_replay_records_the_rules_it_was_played_with() {
+
+    let rules = Rules {
+        wall_speed: 400.0,
+        base_ball_speed: 150.0,
+        ..Rules::default()
+    };
+
+    let (replay, hash) = recorded_replay(rules);
+
+    assert_eq!(replay.rules, rules);
+
+    let loaded = Replay::from_bytes(&replay.to_bytes()).unwrap();
+
+    assert_eq!(loaded.play().map(|state| state.state_hash()), Ok(hash));
+
+}
+
+#[test]
+fn test_changed_replay_fails_verification() {
+
+    let (mut replay, hash) = recorded_replay(Rules::default());
+
+    replay.actions[0].click = Point2::new(610.0, 300.0);
+
+    assert!(match replay.play() {
+        Err(ReplayError::HashMismatch { expected, .. }) => expected == hash,
+        _ => false
+    });
+
+}
+
+#[test]
+fn test_replay_bytes_round_trip() {
+
+    let rules = Rules {
+        clear_percent: 80,
+        on_time_out: TimeOutRule::GameOver,
+        ..Rules::default()
+    };
+
+    let mut replay = Replay::new(rules, 99, 3, playfield());
+    replay.frames = 1000;
+    replay.final_hash = 0x0123_4567_89ab_cdef;
+    replay.actions = vec![
+        WallAction { frame: 12, click: Point2::new(10.5, 20.25), orientation: Orientation::Horizontal },
+        WallAction { frame: 500, click: Point2::new(700.0, 1.0), orientation: Orientation::Vertical }
+    ];
+
+    let bytes = replay.to_bytes();
+
+    assert_eq!(bytes.len(), 106 + 2 * 13);
+    assert_eq!(Replay::from_bytes(&bytes), Ok(replay));
+
+}
+
+#[test]
+fn test_invalid_replay_bytes() {
+
+    let mut future = Replay::new(Rules::default(), 1, 1, playfield());
+    future.version = VERSION + 1;
+
+    let bytes = Replay::new(Rules::default(), 1, 1, playfield()).to_bytes();
+
+    assert_eq!(Replay::from_bytes(b"JUNK"), Err(ReplayError::NotAReplay));
+    assert_eq!(Replay::from_bytes(&bytes[..20]), Err(ReplayError::Truncated));
+    assert_eq!(Replay::from_bytes(&future.to_bytes()), Err(ReplayError::UnknownVersion(VERSION + 1)));
+    assert_eq!(future.play().map(|_| ()), Err(ReplayError::UnknownVersion(VERSION + 1)));
+
+}
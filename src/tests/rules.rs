@@ -0,0 +1,192 @@
+use nalgebra::{Point2};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use super::{playfield, simplest_game_state};
+use crate::game::{GameState, GrowingWall, LevelStatus, Orientation, Rectangle, Rules, TimeOutRule};
+
+#[test]
+fn test_level_starts_with_one_more_ball_and_life_than_its_number() {
+
+    let state = GameState::new_level(3, playfield(), Rules::default(), &mut ChaCha8Rng::seed_from_u64(0));
+
+    assert_eq!(state.level, 3);
+    assert_eq!(state.lives, 4);
+    assert_eq!(state.balls().count(), 4);
+    assert_eq!(state.playfield_area, 800 * 600);
+    assert_eq!(state.status(), LevelStatus::Playing);
+
+    for ball in state.balls() {
+        assert!(state.live_areas[0].ball_disc_is_inside(*ball));
+    }
+
+}
+
+#[test]
+fn test_level_complete_once_three_quarters_captured() {
+
+    let mut state = simplest_game_state();
+
+    state.add_wall(Rectangle {
+        top_left: Point2::new(0, 60),
+        bottom_right: Point2::new(100, 80)
+    }).unwrap();
+
+    assert_eq!(state.captured_area(), 80 * 100);
+    assert_eq!(state.status(), LevelStatus::Complete);
+
+}
+
+#[test]
+fn test_level_not_complete_below_three_quarters_captured() {
+
+    let mut state = simplest_game_state();
+
+    state.add_wall(Rectangle {
+        top_left: Point2::new(0, 40),
+        bottom_right: Point2::new(100, 60)
+    }).unwrap();
+
+    assert_eq!(state.captured_area(), 60 * 100);
+    assert_eq!(state.status(), LevelStatus::Playing);
+
+}
+
+#[test]
+fn test_destroyed_wall_costs_a_life() {
+
+    let mut state = simplest_game_state();
+
+    state.growing_wall = Some(GrowingWall::new(
+        Rectangle {
+            top_left: Point2::new(40, 0),
+            bottom_right: Point2::new(60, 100)
+        },
+        Point2::new(50.0, 20.0),
+        Orientation::Vertical,
+        100.0
+    ));
+
+    for _ in 0..60 {
+        state.update(1.0 / 60.0);
+    }
+
+    assert_eq!(state.growing_wall, None);
+    assert_eq!(state.lives, 1);
+    assert_eq!(state.status(), LevelStatus::Playing);
+
+}
+
+#[test]
+fn test_game_over_when_out_of_lives() {
+
+    let mut state = simplest_game_state();
+    state.lives = 1;
+
+    state.growing_wall = Some(GrowingWall::new(
+        Rectangle {
+            top_left: Point2::new(40, 0),
+            bottom_right: Point2::new(60, 100)
+        },
+        Point2::new(50.0, 20.0),
+        Orientation::Vertical,
+        100.0
+    ));
+
+    for _ in 0..60 {
+        state.update(1.0 / 60.0);
+    }
+
+    assert_eq!(state.lives, 0);
+    assert_eq!(state.status(), LevelStatus::GameOver);
+
+}
+
+#[test]
+fn test_time_limit_per_level() {
+
+    let rules = Rules {
+        base_time_limit: 60.0,
+        time_limit_per_level: 20.0,
+        ..Rules::default()
+    };
+
+    let state = GameState::new_level(2, playfield(), rules, &mut ChaCha8Rng::seed_from_u64(0));
+
+    assert_eq!(state.time_remaining, 100.0);
+
+}
+
+#[test]
+fn test_ball_speed_per_level() {
+
+    let rules = Rules {
+        base_ball_speed: 40.0,
+        ball_speed_per_level: 30.0,
+        ..Rules::default()
+    };
+
+    let state = GameState::new_level(2, playfield(), rules, &mut ChaCha8Rng::seed_from_u64(0));
+
+    for ball in state.balls() {
+        assert!((ball.movement.norm() - 100.0).abs() < 0.001);
+    }
+
+}
+
+#[test]
+fn test_update_advances_timer() {
+
+    let mut state = simplest_game_state();
+    state.time_remaining = 10.0;
+
+    state.update(0.5);
+
+    assert_eq!(state.time_remaining, 9.5);
+
+}
+
+#[test]
+fn test_time_out_costs_a_life() {
+
+    let mut state = simplest_game_state();
+    state.rules.on_time_out = TimeOutRule::LoseLife;
+    state.time_remaining = 0.25;
+
+    state.update(0.5);
+
+    assert_eq!(state.lives, 1);
+    assert_eq!(state.time_remaining, state.rules.time_limit(1));
+    assert_eq!(state.status(), LevelStatus::Playing);
+
+}
+
+#[test]
+fn test_time_out_ends_game() {
+
+    let mut state = simplest_game_state();
+    state.rules.on_time_out = TimeOutRule::GameOver;
+    state.time_remaining = 0.25;
+
+    state.update(0.5);
+
+    assert_eq!(state.lives, 2);
+    assert_eq!(state.time_remaining, 0.0);
+    assert_eq!(state.status(), LevelStatus::GameOver);
+
+}
+
+#[test]
+fn test_sandbox_state_never_times_out() {
+
+    let mut state = simplest_game_state();
+
+    for _ in 0..200 {
+        state.update(1.0);
+    }
+
+    assert_eq!(state.time_remaining, f32::INFINITY);
+    assert_eq!(state.lives, 2);
+    assert_eq!(state.status(), LevelStatus::Playing);
+
+}
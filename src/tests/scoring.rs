@@ -0,0 +1,53 @@
+use nalgebra::{Point2};
+
+use super::{simplest_game_state};
+use crate::game::{Rectangle};
+use crate::scoring::{ScoreBreakdown};
+
+#[test]
+fn test_points_for_captured_area() {
+
+    let mut state = simplest_game_state();
+
+    state.add_wall(Rectangle {
+        top_left: Point2::new(0, 40),
+        bottom_right: Point2::new(100, 60)
+    }).unwrap();
+
+    // 40 x 100 captured is 10 cells
+    assert_eq!(state.score.captured, 100);
+
+}
+
+#[test]
+fn test_level_summary_bonuses() {
+
+    let mut state = simplest_game_state();
+
+    state.add_wall(Rectangle {
+        top_left: Point2::new(0, 60),
+        bottom_right: Point2::new(100, 80)
+    }).unwrap();
+
+    state.time_remaining = 12.5;
+
+    // 60 x 100 captured, plus the wall, is 80% of the area
+    assert_eq!(state.level_summary(), ScoreBreakdown {
+        captured: 150,
+        fill_bonus: 500,
+        life_bonus: 1000,
+        time_bonus: 120
+    });
+
+    assert_eq!(state.level_summary().total(), 1770);
+
+}
+
+#[test]
+fn test_no_fill_bonus_below_clear_threshold() {
+
+    let state = simplest_game_state();
+
+    assert_eq!(state.level_summary().fill_bonus, 0);
+
+}
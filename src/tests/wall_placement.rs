@@ -0,0 +1,122 @@
+use nalgebra::{Point2};
+
+use super::{l_shaped_live_area};
+use super::fixture_parser::{parse_live_area};
+use crate::game::{wall_from_click, GameState, Orientation, Rectangle, WallPlacementError};
+
+fn rectangle(top_left: (i16, i16), bottom_right: (i16, i16)) -> Rectangle {
+    Rectangle {
+        top_left: Point2::new(top_left.0, top_left.1),
+        bottom_right: Point2::new(bottom_right.0, bottom_right.1)
+    }
+}
+
+#[test]
+fn test_vertical_wall_snaps_to_grid() {
+
+    let live_area = l_shaped_live_area();
+
+    let wall = live_area.wall_from_click(Point2::new(73.0, 85.0), Orientation::Vertical);
+
+    assert_eq!(wall, Some(rectangle((60, 0), (80, 100))));
+
+}
+
+#[test]
+fn test_vertical_wall_stops_at_nearest_edge() {
+
+    let live_area = l_shaped_live_area();
+
+    let wall = live_area.wall_from_click(Point2::new(45.0, 85.0), Orientation::Vertical);
+
+    assert_eq!(wall, Some(rectangle((40, 60), (60, 100))));
+
+}
+
+#[test]
+fn test_horizontal_wall_stops_at_nearest_edge() {
+
+    let live_area = l_shaped_live_area();
+
+    let wall = live_area.wall_from_click(Point2::new(75.0, 30.0), Orientation::Horizontal);
+
+    assert_eq!(wall, Some(rectangle((60, 20), (100, 40))));
+
+}
+
+#[test]
+fn test_wall_from_click_outside_live_area() {
+
+    let live_area = l_shaped_live_area();
+
+    let wall = live_area.wall_from_click(Point2::new(30.0, 30.0), Orientation::Horizontal);
+
+    assert_eq!(wall, None);
+
+}
+
+#[test]
+fn test_wall_from_click_finds_live_area() {
+
+    let live_areas = vec![
+        parse_live_area("
+= = = =
+=     =
+=     =
+=     =
+=     =
+=     =
+= = = =
+"),
+        parse_live_area("
+        = = =
+        =   =
+        =   =
+        =   =
+        =   =
+        =   =
+        = = =
+")
+    ];
+
+    assert_eq!(
+        wall_from_click(&live_areas, Point2::new(90.0, 50.0), Orientation::Horizontal),
+        Ok((1, rectangle((80, 40), (100, 60))))
+    );
+
+    assert_eq!(
+        wall_from_click(&live_areas, Point2::new(70.0, 50.0), Orientation::Horizontal),
+        Err(WallPlacementError::OutsideLiveAreas)
+    );
+
+}
+
+#[test]
+fn test_start_wall_rejects_second_wall() {
+
+    let mut state = GameState::new(vec![l_shaped_live_area()]);
+
+    assert_eq!(state.start_wall(Point2::new(73.0, 85.0), Orientation::Vertical), Ok(()));
+    assert!(state.growing_wall.is_some());
+
+    assert_eq!(
+        state.start_wall(Point2::new(45.0, 85.0), Orientation::Vertical),
+        Err(WallPlacementError::WallInProgress)
+    );
+
+}
+
+#[test]
+fn test_start_wall_rejects_click_on_built_wall() {
+
+    let mut state = GameState::new(vec![l_shaped_live_area()]);
+
+    state.add_wall(rectangle((60, 0), (80, 100))).unwrap();
+
+    assert_eq!(
+        state.start_wall(Point2::new(70.0, 50.0), Orientation::Horizontal),
+        Err(WallPlacementError::OutsideLiveAreas)
+    );
+    assert!(state.growing_wall.is_none());
+
+}